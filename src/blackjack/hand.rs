@@ -0,0 +1,66 @@
+// Hand values
+// ---------------------------------------------------------
+// Aces count as 1, and one of them as 11 when that keeps the hand at 21
+// or under, making the hand soft.
+
+use super::{Card, ACE};
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct HandValue {
+    pub total: u8,
+    /// An ace counts as 11
+    pub is_soft: bool,
+    /// 21 with the first two cards, not after a split
+    pub is_blackjack: bool,
+}
+
+impl HandValue {
+    pub fn of(cards: &[Card]) -> HandValue {
+        let hard: u8 = cards.iter().map(|&card| points(card)).sum();
+        let is_soft = cards.contains(&ACE) && hard + 10 <= 21;
+        let total = if is_soft { hard + 10 } else { hard };
+        HandValue {
+            total,
+            is_soft,
+            is_blackjack: total == 21 && cards.len() == 2,
+        }
+    }
+
+    pub fn is_bust(&self) -> bool {
+        self.total > 21
+    }
+}
+
+// an ace counts as 1, the jack, queen and king as 10
+fn points(card: Card) -> u8 {
+    match card {
+        11..=13 => 10,
+        ACE => 1,
+        _ => card,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn aces_are_worth_11_while_the_hand_does_not_bust() {
+        let value = HandValue::of(&[ACE, 9, 5]);
+        assert_eq!(15, value.total);
+        assert!(!value.is_soft);
+        let value = HandValue::of(&[ACE, 6]);
+        assert_eq!((17, true), (value.total, value.is_soft));
+        let value = HandValue::of(&[ACE, ACE, 9]);
+        assert_eq!((21, true), (value.total, value.is_soft));
+        assert!(!value.is_blackjack);
+        assert!(HandValue::of(&[10, 9, 5]).is_bust());
+    }
+
+    #[test]
+    fn blackjack_takes_two_cards() {
+        assert!(HandValue::of(&[13, ACE]).is_blackjack);
+        assert!(!HandValue::of(&[7, 7, 7]).is_blackjack);
+        assert_eq!(0, HandValue::of(&[]).total);
+    }
+}
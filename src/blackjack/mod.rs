@@ -1,19 +1,37 @@
+mod hand;
+mod observation;
 #[cfg(feature = "python")]
 pub mod python;
+mod rules;
+mod shoe;
+pub use hand::HandValue;
+pub use observation::{Encoding, Observation};
+pub use rules::{BlackjackRules, DoubleOn, Surrender};
+pub use shoe::{Shoe, MAX_DECKS};
 
 #[cfg(feature = "wasm")]
 use serde::{Deserialize, Serialize};
 #[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
 
-use rand::Rng;
-
 // Types
 pub type Deck = Vec<u8>;
 pub type Card = u8;
 pub type Hand = Vec<Card>;
 #[allow(dead_code)]
 pub type ObservationVals = [[[u8; 3]; 3]; 3];
+pub type State<'a> = (
+    &'a Vec<Vec<Hand>>,
+    &'a Vec<Vec<HandValue>>,
+    &'a Vec<Vec<u64>>,
+    &'a Vec<Vec<bool>>,
+    &'a Vec<Vec<bool>>,
+    u8,
+    &'a Vec<usize>,
+    &'a Vec<bool>,
+    &'a Vec<bool>,
+    &'a Vec<bool>,
+);
 
 // Constants
 #[allow(dead_code)]
@@ -21,37 +39,70 @@ pub const DECK: [u8; 52] = [
     2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 2, 3,
     4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14,
 ];
+pub const ACE: Card = 14;
+// stand, hit, double down, split, 8 bets, insurance, even money, surrender
+pub const NUMBER_ACTIONS: usize = 15;
+pub const INSURANCE: u8 = 12;
+pub const EVEN_MONEY: u8 = 13;
+pub const SURRENDER: u8 = 14;
+
+// Every player, the dealer at index 0, holds one hand or more after
+// splitting, each hand with its own value, bet and state
 #[derive(Debug)]
 #[allow(dead_code)]
 pub struct TwentyOne {
-    deck: Deck,
-    players_hand: Vec<Hand>,
-    players_value: Vec<u8>,
-    players_bet: Vec<u64>,
-    players_planted: Vec<bool>,
-    players_busted: Vec<bool>,
+    shoe: Shoe,
+    players_hand: Vec<Vec<Hand>>,
+    players_value: Vec<Vec<HandValue>>,
+    players_bet: Vec<Vec<u64>>,
+    players_planted: Vec<Vec<bool>>,
+    players_busted: Vec<Vec<bool>>,
+    // the hand each player is playing
+    players_active_hand: Vec<usize>,
+    // side bets and early ends of the first decision
+    players_insured: Vec<bool>,
+    players_even_money: Vec<bool>,
+    players_surrendered: Vec<bool>,
+    // the bet before doubling, insurance and even money being worked out
+    // from it
+    players_base_bet: Vec<u64>,
+    rules: BlackjackRules,
+    encoding: Encoding,
+    // whether observations hold the counts of the cards seen
+    counts: bool,
     total_players: u8,
     current_player: u8,
 }
 
 impl TwentyOne {
     #[allow(dead_code)]
-    pub fn new(n_players: usize) -> TwentyOne {
+    pub fn new(n_players: usize, rules: BlackjackRules) -> TwentyOne {
         let n_players = n_players + 1;
         TwentyOne {
-            deck: DECK.to_vec(),
-            players_hand: vec![vec![]; n_players],
-            players_value: vec![0; n_players],
-            players_bet: vec![0; n_players],
-            players_planted: vec![false; n_players],
-            players_busted: vec![false; n_players],
+            shoe: Shoe::default(),
+            players_hand: vec![vec![vec![]]; n_players],
+            players_value: vec![vec![HandValue::default()]; n_players],
+            players_bet: vec![vec![0]; n_players],
+            players_planted: vec![vec![false]; n_players],
+            players_busted: vec![vec![false]; n_players],
+            players_active_hand: vec![0; n_players],
+            players_insured: vec![false; n_players],
+            players_even_money: vec![false; n_players],
+            players_surrendered: vec![false; n_players],
+            players_base_bet: vec![0; n_players],
+            rules,
+            encoding: Encoding::Legacy,
+            counts: false,
             total_players: n_players as u8,
             current_player: 1,
         }
     }
 
+    /// Hands, values, bets, planted and busted flags of every hand of every
+    /// player, the current player, the hand each player is playing and
+    /// whether each player took insurance, even money or surrendered
     #[allow(dead_code)]
-    pub fn get_state(&self) -> (&Vec<Hand>, &Vec<u8>, &Vec<u64>, &Vec<bool>, &Vec<bool>, u8) {
+    pub fn get_state(&self) -> State<'_> {
         (
             &self.players_hand,
             &self.players_value,
@@ -59,46 +110,87 @@ impl TwentyOne {
             &self.players_planted,
             &self.players_busted,
             self.current_player,
+            &self.players_active_hand,
+            &self.players_insured,
+            &self.players_even_money,
+            &self.players_surrendered,
         )
     }
 
+    #[allow(dead_code)]
+    pub fn rules(&self) -> &BlackjackRules {
+        &self.rules
+    }
+
+    #[allow(dead_code)]
+    pub fn observation_size(&self) -> Option<usize> {
+        self.encoding.len(self.counts)
+    }
+
+    /// Encodes observations with `encoding`, followed by the running and
+    /// true counts and the cards of each rank left in the shoe when
+    /// `counts` is set. `Encoding::Legacy` by default.
+    #[allow(dead_code)]
+    pub fn set_observation(&mut self, encoding: Encoding, counts: bool) {
+        self.encoding = encoding;
+        self.counts = counts;
+    }
+
+    /// Deals from `shoe` from the next `reset` on, by default a single deck
+    /// shuffled before every round
+    #[allow(dead_code)]
+    pub fn set_shoe(&mut self, shoe: Shoe) {
+        self.shoe = shoe;
+    }
+
+    #[allow(dead_code)]
+    pub fn shoe(&self) -> &Shoe {
+        &self.shoe
+    }
+
     #[allow(dead_code)]
     pub fn get_total_players(&self) -> u8 {
         self.total_players
     }
 
     #[allow(dead_code)]
-    pub fn step(&mut self, action: u8, change_player: bool) -> (ObservationVals, Vec<i64>, bool) {
+    pub fn step(&mut self, action: u8, change_player: bool) -> (Observation, Vec<f64>, bool) {
         let c_p = self.current_player as usize;
-        if action > 12 || !self.legal_actions()[action as usize] {
+        if action as usize >= NUMBER_ACTIONS || !self.legal_actions()[action as usize] {
             panic!("invalid action");
         }
-        if self.players_bet[c_p] == 0 {
+        if self.players_bet[c_p][0] == 0 {
             self.deal(action);
-            return (
-                self.get_observation(),
-                vec![0; self.total_players as usize],
-                self.all_done(),
-            );
-        }
-        if !(self.players_busted[c_p] || self.players_planted[c_p]) {
-            if action == 2 {
+        } else if !self.player_done() {
+            let hand = self.players_active_hand[c_p];
+            if action == INSURANCE {
+                self.players_insured[c_p] = true;
+            } else if action == EVEN_MONEY {
+                self.players_even_money[c_p] = true;
+                self.players_planted[c_p][hand] = true;
+            } else if action == SURRENDER {
+                self.players_surrendered[c_p] = true;
+                self.players_planted[c_p][hand] = true;
+            } else if action == 3 {
+                self.split();
+            } else if action == 2 {
                 self.double_down();
-                self.step(1, false);
+                self.draw_to(c_p, hand);
+                self.players_planted[c_p][hand] = true;
             } else if action == 0 {
-                self.players_planted[c_p] = true;
+                self.players_planted[c_p][hand] = true;
             } else if action == 1 {
-                self.players_hand[c_p] = self.hit(self.players_hand[c_p].clone());
-                self.players_value[c_p] = self.deal_card_value(&self.players_hand[c_p]);
+                self.draw_to(c_p, hand);
             }
-            self.players_busted[c_p] = self.is_busted();
+            self.players_busted[c_p][hand] = self.is_busted_hand(c_p, hand);
+            self.next_hand();
         }
         let all_done = self.all_done();
         let reward;
         if all_done {
             reward = self.stand();
         } else {
-            reward = vec![0; self.total_players as usize];
+            reward = vec![0.0; self.total_players as usize];
         }
         let observation = self.get_observation();
         if change_player {
@@ -119,152 +211,209 @@ impl TwentyOne {
         let total_players = self.total_players as usize;
         let mut count = 1;
         for player in 1..total_players {
-            if (self.players_value[player] > 21 && self.players_value[player] != 100)
-                || self.players_planted[player]
-                || self.players_busted[player]
-            {
+            if (0..self.players_hand[player].len()).all(|hand| {
+                self.players_value[player][hand].is_bust()
+                    || self.players_planted[player][hand]
+                    || self.players_busted[player][hand]
+            }) {
                 count += 1;
             }
         }
         total_players == count
     }
 
-    fn stand(&mut self) -> Vec<i64> {
+    fn stand(&mut self) -> Vec<f64> {
         self.dealer_plays();
-        let mut res: Vec<i64> = Vec::new();
+        let dealer_blackjack = self.players_value[0][0].is_blackjack;
+        let mut res: Vec<f64> = Vec::new();
         for player in 0..self.total_players as usize {
-            match self.get_reward(player) {
-                2 => {
-                    let reward = 3 * self.players_bet[player];
-                    res.push((reward / 3 * 2) as i64);
-                }
-                1 => {
-                    let reward = 2 * self.players_bet[player];
-                    res.push((reward / 2) as i64);
-                }
-                0 => {
-                    res.push(0);
-                }
-                -1 => {
-                    let reward = self.players_bet[player];
-                    res.push(-(reward as i64));
-                }
-                _ => {}
+            let bet = self.players_base_bet[player] as f64;
+            let mut total = 0.0;
+            // insurance pays 2:1 on half the bet
+            if self.players_insured[player] {
+                total += if dealer_blackjack { bet } else { -bet / 2.0 };
+            }
+            if self.players_even_money[player] {
+                res.push(total + bet);
+                continue;
+            }
+            if self.players_surrendered[player] {
+                let lost = match self.rules.surrender {
+                    Surrender::Late if dealer_blackjack => bet,
+                    _ => bet / 2.0,
+                };
+                res.push(total - lost);
+                continue;
+            }
+            // every hand of a split is paid on its own
+            let mut hands = 0.0;
+            for hand in 0..self.players_hand[player].len() {
+                let bet = self.players_bet[player][hand] as f64;
+                hands += match self.get_reward(player, hand) {
+                    2 => bet * self.rules.blackjack_pays,
+                    1 => bet,
+                    -1 => -bet,
+                    _ => 0.0,
+                };
+            }
+            // a peeking dealer ends the round before anything is doubled or
+            // split
+            if dealer_blackjack && self.rules.dealer_peeks {
+                hands = hands.max(-bet);
             }
+            res.push(total + hands);
         }
         res
     }
 
-    fn get_observation(&self) -> ObservationVals {
-        let mut res: ObservationVals = [[[0; 3]; 3]; 3];
-        let vals: [u8; 3] = [self.players_value[1], self.players_hand[0][0], 0];
-        for i in 0..3 {
-            for j in 0..3 {
-                for k in 0..3 {
-                    res[i][j][k] = vals[i];
-                }
-            }
-        }
-        res
+    pub fn legal_actions(&self) -> [bool; NUMBER_ACTIONS] {
+        self.legal_actions_of(self.current_player as usize)
     }
 
-    pub fn legal_actions(&self) -> [bool; 12] {
+    fn legal_actions_of(&self, c_p: usize) -> [bool; NUMBER_ACTIONS] {
+        let mut legal_actions = [false; NUMBER_ACTIONS];
         // <----bet---->
         // the first step is to bet
-        if self.players_bet[self.current_player as usize] == 0 {
-            return [
-                false, false, false, false, true, true, true, true, true, true, true, true,
-            ];
+        // 4 - 11
+        if self.players_bet[c_p][0] == 0 {
+            for legal in legal_actions[4..12].iter_mut() {
+                *legal = true;
+            }
+            return legal_actions;
         }
         // <----plays---->
         // 0 = stand
         // 1 = HIT
         // 2 = double down
-        // 3 = pull apart (currently disabled)
-        let c_p = self.current_player as usize;
-        if self.players_hand[c_p].len() == 2 {
-            return [
-                true, true, true, false, false, false, false, false, false, false, false, false,
-            ];
+        // 3 = split a pair into two hands
+        // 12 = insurance, when the dealer shows an ace
+        // 13 = even money, for a blackjack when the dealer shows an ace
+        // 14 = surrender
+        if self.hands_done(c_p) {
+            return legal_actions;
+        }
+        legal_actions[0] = true;
+        legal_actions[1] = true;
+        let hand = &self.players_hand[c_p][self.players_active_hand[c_p]];
+        if hand.len() == 2 {
+            let split = self.players_hand[c_p].len() > 1;
+            let total = self.players_value[c_p][self.players_active_hand[c_p]].total;
+            legal_actions[2] =
+                (self.rules.double_after_split || !split) && self.rules.double_on.allows(total);
+            legal_actions[3] = card_points(hand[0]) == card_points(hand[1])
+                && self.players_hand[c_p].len() < self.rules.max_hands;
         }
-        if self.players_busted[c_p] || self.players_planted[c_p] {
-            return [
-                false, false, false, false, false, false, false, false, false, false, false, false,
-            ];
+        // the first decision, before splitting or drawing
+        if self.players_hand[c_p].len() == 1 && hand.len() == 2 {
+            let dealer_shows_ace = self.players_hand[0][0].first() == Some(&ACE);
+            legal_actions[INSURANCE as usize] = dealer_shows_ace && !self.players_insured[c_p];
+            legal_actions[EVEN_MONEY as usize] = dealer_shows_ace
+                && !self.players_insured[c_p]
+                && self.players_value[c_p][0].is_blackjack;
+            legal_actions[SURRENDER as usize] = self.rules.surrender != Surrender::NotAllowed;
         }
-        [
-            true, true, false, false, false, false, false, false, false, false, false, false,
-        ]
+        legal_actions
     }
 
     fn double_down(&mut self) {
         let c_p = self.current_player as usize;
-        self.players_bet[c_p] *= 2;
+        let hand = self.players_active_hand[c_p];
+        self.players_bet[c_p][hand] *= 2;
     }
 
-    // won->1 dealer_wins->-1 draw->0 wins2:3->2
-    fn get_reward(&self, player: usize) -> i8 {
-        let player = player;
-        if self.players_value[player] == 100 && self.players_value[0] != 100 {
-            return 2;
+    // the second card of the pair starts a new hand with the same bet, played
+    // after this one. Split aces get one card each and no more.
+    fn split(&mut self) {
+        let c_p = self.current_player as usize;
+        let hand = self.players_active_hand[c_p];
+        let card = self.players_hand[c_p][hand].pop().unwrap();
+        let bet = self.players_bet[c_p][hand];
+        self.players_hand[c_p].insert(hand + 1, vec![card]);
+        self.players_value[c_p].insert(hand + 1, HandValue::default());
+        self.players_bet[c_p].insert(hand + 1, bet);
+        self.players_planted[c_p].insert(hand + 1, false);
+        self.players_busted[c_p].insert(hand + 1, false);
+        self.players_value[c_p][hand + 1] = self.hand_value(c_p, hand + 1);
+        self.draw_to(c_p, hand);
+        self.players_planted[c_p][hand] = card == ACE;
+    }
+
+    // once the hand being played is over, moves on to the next hand of the
+    // split, dealing its second card
+    fn next_hand(&mut self) {
+        let c_p = self.current_player as usize;
+        loop {
+            let hand = self.players_active_hand[c_p];
+            let done = self.players_planted[c_p][hand] || self.players_busted[c_p][hand];
+            if !done || hand + 1 == self.players_hand[c_p].len() {
+                break;
+            }
+            self.players_active_hand[c_p] += 1;
+            let next = hand + 1;
+            if self.players_hand[c_p][next].len() == 1 {
+                self.draw_to(c_p, next);
+                self.players_planted[c_p][next] = self.players_hand[c_p][next][0] == ACE;
+            }
         }
-        if self.players_value[player] == 100 && self.players_value[0] == 100 {
-            return 0;
+    }
+
+    // won->1 dealer_wins->-1 draw->0 blackjack->2
+    fn get_reward(&self, player: usize, hand: usize) -> i8 {
+        let value = self.players_value[player][hand];
+        let dealer = self.players_value[0][0];
+        if value.is_blackjack {
+            return if dealer.is_blackjack { 0 } else { 2 };
         }
-        if self.players_value[player] <= 21 && self.players_value[0] < self.players_value[player] {
-            return 1;
+        // a busted hand loses even when the dealer busts too
+        if value.is_bust() || dealer.is_blackjack {
+            return -1;
         }
-        if self.players_value[player] <= 21
-            && (self.players_value[0] > 21 && self.players_value[0] != 100)
-        {
+        if dealer.is_bust() || value.total > dealer.total {
             return 1;
         }
-        if self.players_value[player] > 21
-            && (self.players_value[0] > 21 && self.players_value[0] != 100)
-        {
-            return 0;
-        }
-        if self.players_value[player] > 21 {
-            return -1;
-        }
-        if self.players_value[player] == self.players_value[0] {
+        if value.total == dealer.total {
             return 0;
         }
         return -1;
     }
 
-    // Bet 0->$1, 1->$5, 2->$10, 3->$25, 4->$50, 5->$100, 6->$500, 7->$1000
+    // Bet 4 to 11 are the bets of the rules, by default 4->$1, 5->$5,
+    // 6->$10, 7->$25, 8->$50, 9->$100, 10->$500, 11->$1000
     fn deal(&mut self, bet: u8) -> bool {
         if bet > 11 {
             panic!("invalid action");
         }
         let c_p = self.current_player as usize;
-        let bets = [1, 5, 10, 25, 50, 100, 500, 1000];
-        let playerbet = bets[bet as usize - 4];
-        self.players_bet[c_p] = playerbet;
+        let playerbet = self.rules.bets[bet as usize - 4];
+        self.players_bet[c_p][0] = playerbet;
+        self.players_base_bet[c_p] = playerbet;
         if self.current_player == 1 {
             for _i in 0..2 {
-                self.players_hand[0] = self.hit(self.players_hand[0].clone());
-                self.players_value[0] = self.deal_card_value(&self.players_hand[0]);
+                self.draw_to(0, 0);
             }
         }
-        self.step(1, false);
-        self.step(1, true);
+        for _i in 0..2 {
+            self.draw_to(c_p, 0);
+        }
         true
     }
 
     #[allow(dead_code)]
-    pub fn reset(&mut self) -> ObservationVals {
+    pub fn reset(&mut self) -> Observation {
         let t_p = self.total_players as usize;
-        let observation = [[[0; 3]; 3]; 3];
-        self.deck = DECK.to_vec();
-        self.players_hand = vec![vec![]; t_p];
-        self.players_value = vec![0; t_p];
-        self.players_bet = vec![0; t_p];
-        self.players_planted = vec![false; t_p];
-        self.players_busted = vec![false; t_p];
+        self.shoe.start_round();
+        self.players_hand = vec![vec![vec![]]; t_p];
+        self.players_value = vec![vec![HandValue::default()]; t_p];
+        self.players_bet = vec![vec![0]; t_p];
+        self.players_planted = vec![vec![false]; t_p];
+        self.players_busted = vec![vec![false]; t_p];
+        self.players_active_hand = vec![0; t_p];
+        self.players_insured = vec![false; t_p];
+        self.players_even_money = vec![false; t_p];
+        self.players_surrendered = vec![false; t_p];
+        self.players_base_bet = vec![0; t_p];
         self.current_player = 1;
-        observation
+        self.get_observation()
     }
 
     #[allow(dead_code)]
@@ -272,78 +421,319 @@ impl TwentyOne {
         self.current_player
     }
 
+    /// Whether every hand of the current player is over
     pub fn player_done(&self) -> bool {
-        let c_p = self.current_player as usize;
-        self.players_busted[c_p] || self.players_planted[c_p]
+        self.hands_done(self.current_player as usize)
+    }
+
+    fn hands_done(&self, player: usize) -> bool {
+        (0..self.players_hand[player].len())
+            .all(|hand| self.players_busted[player][hand] || self.players_planted[player][hand])
     }
 
     fn hit(&mut self, mut hand: Hand) -> Hand {
-        let mut rng = rand::thread_rng();
-        if self.deck.len() == 0 {
-            self.deck = DECK.to_vec();
-        }
-        let card = self.deck.remove(rng.gen_range(0..self.deck.len()));
-        hand.push(card);
+        hand.push(self.shoe.draw());
         hand
     }
 
-    // come back 100 when it's blackjack
-    fn deal_card_value(&self, hand: &Hand) -> u8 {
-        let mut value = 0;
-        for card in hand.iter() {
-            if *card == 11 || *card == 12 || *card == 13 {
-                value += 10;
-            } else if *card == 14 {
-                if value >= 11 {
-                    value += 1;
-                } else {
-                    value += 11;
-                }
-            } else {
-                value += card;
-            }
-        }
-        if value == 21 && hand.len() == 2 {
-            return 100;
+    fn draw_to(&mut self, player: usize, hand: usize) {
+        self.players_hand[player][hand] = self.hit(self.players_hand[player][hand].clone());
+        self.players_value[player][hand] = self.hand_value(player, hand);
+    }
+
+    // two cards making 21 after a split are not a blackjack
+    fn hand_value(&self, player: usize, hand: usize) -> HandValue {
+        let mut value = HandValue::of(&self.players_hand[player][hand]);
+        if self.players_hand[player].len() > 1 {
+            value.is_blackjack = false;
         }
         value
     }
 
+    // the dealer only draws while some hand of a player is still standing
     fn dealer_plays(&mut self) {
-        let players_still_alive = !self
-            .players_value
+        let players_still_alive = self.players_value[1..]
             .iter()
-            .filter(|x| x <= &&21 || x == &&100)
-            .collect::<Vec<&u8>>()
-            .is_empty();
+            .flatten()
+            .any(|value| !value.is_bust());
         if players_still_alive {
-            while self.players_value[0] <= 16 {
-                self.players_hand[0] = self.hit(self.players_hand[0].clone());
-                self.players_value[0] = self.deal_card_value(&self.players_hand[0]);
+            loop {
+                let value = self.players_value[0][0];
+                let soft_17 = value.total == 17 && value.is_soft;
+                if value.total > 16 && !(soft_17 && self.rules.dealer_hits_soft_17) {
+                    break;
+                }
+                self.draw_to(0, 0);
             }
         }
     }
 
     fn is_busted(&self) -> bool {
         let c_p = self.current_player as usize;
-        if self.players_value[c_p] > 21 && self.players_value[c_p] != 100 {
-            true
-        } else {
-            false
-        }
+        self.is_busted_hand(c_p, self.players_active_hand[c_p])
+    }
+
+    fn is_busted_hand(&self, player: usize, hand: usize) -> bool {
+        self.players_value[player][hand].is_bust()
     }
 
     #[allow(dead_code)]
     pub fn render(&self) {
         for player in 1..self.total_players as usize {
-            println!(
-                "Player {} Hand: {:?} Player value: {}",
-                player, self.players_hand[player], self.players_value[player]
-            );
+            for (hand, cards) in self.players_hand[player].iter().enumerate() {
+                println!(
+                    "Player {} Hand {}: {:?} Player value: {} Bet: {}",
+                    player,
+                    hand,
+                    cards,
+                    self.players_value[player][hand].total,
+                    self.players_bet[player][hand]
+                );
+            }
         }
         println!(
             "Dealer Hand: {:?} Dealer value: {}",
-            self.players_hand[0], self.players_value[0]
+            self.players_hand[0][0], self.players_value[0][0].total
         );
     }
 }
+
+// value of a card towards a pair: the ten, jack, queen and king are all tens
+fn card_points(card: Card) -> u8 {
+    match card {
+        11..=13 => 10,
+        ACE => 11,
+        _ => card,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // a single player who bet $1 and holds `cards`
+    fn holding(cards: &[Card]) -> TwentyOne {
+        holding_with(cards, BlackjackRules::default())
+    }
+
+    fn holding_with(cards: &[Card], rules: BlackjackRules) -> TwentyOne {
+        let mut game = TwentyOne::new(1, rules);
+        game.reset();
+        game.step(4, true);
+        game.players_hand[1][0] = cards.to_vec();
+        game.players_value[1][0] = game.hand_value(1, 0);
+        game
+    }
+
+    #[test]
+    fn splitting_a_pair_makes_two_hands() {
+        let mut game = holding(&[8, 8]);
+        assert!(game.legal_actions()[3]);
+        let (_, _, done) = game.step(3, true);
+        assert!(!done);
+        assert_eq!(2, game.players_hand[1].len());
+        assert_eq!(vec![1, 1], game.players_bet[1]);
+        assert_eq!(8, game.players_hand[1][0][0]);
+        // the second hand gets its second card once it is played
+        assert_eq!(vec![8], game.players_hand[1][1]);
+        game.step(0, true);
+        assert_eq!(1, game.players_active_hand[1]);
+        assert_eq!(2, game.players_hand[1][1].len());
+        game.set_observation(Encoding::Legacy, false);
+        let (observation, rewards, done) = game.step(0, true);
+        assert!(done);
+        assert!(rewards[1].abs() <= 2.0);
+        match observation {
+            Observation::Legacy(planes) => {
+                assert_eq!(game.players_value[1][1].total, planes[2][0][1])
+            }
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn split_aces_get_a_single_card() {
+        let mut game = holding(&[ACE, ACE]);
+        let (_, _, done) = game.step(3, true);
+        assert!(done);
+        for hand in 0..2 {
+            assert_eq!(2, game.players_hand[1][hand].len());
+            assert!(game.players_planted[1][hand]);
+            // 21 after a split is not a blackjack
+            assert!(!game.players_value[1][hand].is_blackjack);
+        }
+    }
+
+    #[test]
+    fn pairs_are_split_up_to_the_limit() {
+        // a king and a queen make a pair of tens
+        let mut game = holding(&[13, 12]);
+        assert!(game.legal_actions()[3]);
+        let max_hands = game.rules.max_hands;
+        game.players_hand[1] = vec![vec![10, 10]; max_hands];
+        game.players_value[1] = vec![HandValue::of(&[10, 10]); max_hands];
+        game.players_bet[1] = vec![1; max_hands];
+        game.players_planted[1] = vec![false; max_hands];
+        game.players_busted[1] = vec![false; max_hands];
+        assert!(!game.legal_actions()[3]);
+        let game = holding(&[9, 10]);
+        assert!(!game.legal_actions()[3]);
+    }
+
+    fn dealer_holds(game: &mut TwentyOne, cards: &[Card]) {
+        game.players_hand[0][0] = cards.to_vec();
+        game.players_value[0][0] = game.hand_value(0, 0);
+    }
+
+    #[test]
+    fn insurance_pays_against_a_dealer_blackjack() {
+        let mut game = holding(&[10, 9]);
+        dealer_holds(&mut game, &[ACE, 13]);
+        assert!(game.legal_actions()[INSURANCE as usize]);
+        assert!(!game.legal_actions()[EVEN_MONEY as usize]);
+        let (_, _, done) = game.step(INSURANCE, true);
+        assert!(!done);
+        assert!(!game.legal_actions()[INSURANCE as usize]);
+        // the insurance makes up for the hand lost to the blackjack
+        let (_, rewards, done) = game.step(0, true);
+        assert!(done);
+        assert_eq!(0.0, rewards[1]);
+        // no insurance without an ace
+        let mut game = holding(&[10, 9]);
+        dealer_holds(&mut game, &[10, ACE]);
+        assert!(!game.legal_actions()[INSURANCE as usize]);
+    }
+
+    #[test]
+    fn even_money_pays_a_blackjack_one_to_one() {
+        let mut game = holding(&[ACE, 13]);
+        dealer_holds(&mut game, &[ACE, 12]);
+        assert!(game.legal_actions()[EVEN_MONEY as usize]);
+        let (_, rewards, done) = game.step(EVEN_MONEY, true);
+        assert!(done);
+        assert_eq!(1.0, rewards[1]);
+    }
+
+    #[test]
+    fn surrender_gives_up_half_the_bet() {
+        let mut game = holding(&[10, 6]);
+        dealer_holds(&mut game, &[10, 7]);
+        let (_, rewards, done) = game.step(SURRENDER, true);
+        assert!(done);
+        assert_eq!(-0.5, rewards[1]);
+        // late surrender does not save anything against a blackjack
+        let mut game = holding(&[10, 6]);
+        dealer_holds(&mut game, &[ACE, 13]);
+        assert_eq!(-1.0, game.step(SURRENDER, true).1[1]);
+        let rules = BlackjackRules {
+            surrender: Surrender::Early,
+            ..BlackjackRules::default()
+        };
+        let mut game = holding_with(&[10, 6], rules);
+        dealer_holds(&mut game, &[ACE, 13]);
+        assert_eq!(-0.5, game.step(SURRENDER, true).1[1]);
+        let rules = BlackjackRules {
+            surrender: Surrender::NotAllowed,
+            ..BlackjackRules::default()
+        };
+        let game = holding_with(&[10, 6], rules);
+        assert!(!game.legal_actions()[SURRENDER as usize]);
+        // only the first decision can be surrendered
+        let mut game = holding(&[2, 3]);
+        game.step(1, true);
+        assert!(!game.legal_actions()[SURRENDER as usize]);
+    }
+
+    #[test]
+    fn blackjack_pays_what_the_table_says() {
+        let mut game = holding(&[ACE, 13]);
+        dealer_holds(&mut game, &[10, 7]);
+        assert_eq!(1.5, game.step(0, true).1[1]);
+        let rules = BlackjackRules {
+            blackjack_pays: 1.2,
+            bets: [5, 10, 15, 20, 25, 50, 75, 100],
+            ..BlackjackRules::default()
+        };
+        let mut game = holding_with(&[ACE, 13], rules);
+        assert_eq!(vec![5], game.players_bet[1]);
+        dealer_holds(&mut game, &[10, 7]);
+        assert_eq!(6.0, game.step(0, true).1[1]);
+    }
+
+    #[test]
+    fn dealer_draws_to_soft_17_under_h17() {
+        let mut game = holding(&[10, 9]);
+        dealer_holds(&mut game, &[ACE, 6]);
+        game.step(0, true);
+        assert_eq!(2, game.players_hand[0][0].len());
+        let rules = BlackjackRules {
+            dealer_hits_soft_17: true,
+            ..BlackjackRules::default()
+        };
+        let mut game = holding_with(&[10, 9], rules);
+        dealer_holds(&mut game, &[ACE, 6]);
+        game.step(0, true);
+        assert!(game.players_hand[0][0].len() > 2);
+        // a hard 17 stands either way
+        let rules = BlackjackRules {
+            dealer_hits_soft_17: true,
+            ..BlackjackRules::default()
+        };
+        let mut game = holding_with(&[10, 9], rules);
+        dealer_holds(&mut game, &[10, 7]);
+        game.step(0, true);
+        assert_eq!(2, game.players_hand[0][0].len());
+    }
+
+    #[test]
+    fn doubling_follows_the_table_restrictions() {
+        let rules = BlackjackRules {
+            double_on: DoubleOn::TenToEleven,
+            double_after_split: false,
+            ..BlackjackRules::default()
+        };
+        assert!(holding_with(&[5, 6], rules.clone()).legal_actions()[2]);
+        assert!(!holding_with(&[4, 5], rules.clone()).legal_actions()[2]);
+        let mut game = holding_with(&[8, 8], rules);
+        game.step(3, true);
+        assert!(!game.legal_actions()[2]);
+        let mut game = holding(&[8, 8]);
+        game.step(3, true);
+        assert!(game.legal_actions()[2]);
+    }
+
+    #[test]
+    fn peeking_dealer_only_takes_the_original_bet() {
+        let mut game = holding(&[5, 6]);
+        dealer_holds(&mut game, &[ACE, 13]);
+        assert_eq!(-1.0, game.step(2, true).1[1]);
+        let rules = BlackjackRules {
+            dealer_peeks: false,
+            ..BlackjackRules::default()
+        };
+        let mut game = holding_with(&[5, 6], rules);
+        dealer_holds(&mut game, &[ACE, 13]);
+        assert_eq!(-2.0, game.step(2, true).1[1]);
+    }
+
+    #[test]
+    fn a_busted_hand_loses_even_when_the_dealer_busts() {
+        let mut game = holding(&[10, 6, 9]);
+        dealer_holds(&mut game, &[10, 6, 8]);
+        assert_eq!(-1, game.get_reward(1, 0));
+        // A, 9, 5 is a hard 15, not a bust
+        let mut game = holding(&[ACE, 9, 5]);
+        assert!(!game.players_value[1][0].is_bust());
+        dealer_holds(&mut game, &[10, 6, 8]);
+        assert_eq!(1, game.get_reward(1, 0));
+    }
+
+    #[test]
+    fn doubling_down_takes_one_card() {
+        let mut game = holding(&[5, 6]);
+        let (_, _, done) = game.step(2, true);
+        assert!(done);
+        assert_eq!(3, game.players_hand[1][0].len());
+        assert_eq!(vec![2], game.players_bet[1]);
+    }
+}
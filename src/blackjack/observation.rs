@@ -0,0 +1,210 @@
+// Observations
+// ---------------------------------------------------------
+// What the first player sees of the table. The legacy encoding is the
+// original 3x3x3 array; the others are flat vectors of the total, soft and
+// pair flags of the hand being played, the dealer's up card and the legal
+// actions, optionally followed by the Hi-Lo running and true counts and the
+// cards of each rank left in the shoe. The dealer's hole card stays out of
+// the counts until the round is over.
+
+use super::shoe::{hi_lo, rank_index, RANKS};
+use super::{card_points, Card, ObservationVals, TwentyOne, DECK, NUMBER_ACTIONS};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Encoding {
+    /// The 3x3x3 array: the total of the hand being played, the dealer's
+    /// up card, and the totals of the first six hands followed by the soft,
+    /// blackjack and bust flags of the hand being played
+    Legacy,
+    /// Total, soft flag, pair flag, dealer's up card (2 to 11, 0 before the
+    /// deal) and a flag per action
+    Features,
+    /// The same with the total (0 to 31) and the up card (0 to 11) one-hot
+    /// encoded
+    OneHot,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Observation {
+    Legacy(ObservationVals),
+    Vector(Vec<f32>),
+}
+
+// one-hot slots of the total and the up card
+const TOTALS: usize = 32;
+const UP_CARDS: usize = 12;
+
+impl Encoding {
+    /// Length of the observation vector, `None` for the legacy array
+    pub fn len(&self, counts: bool) -> Option<usize> {
+        let counts = if counts { 2 + RANKS } else { 0 };
+        match self {
+            Encoding::Legacy => None,
+            Encoding::Features => Some(4 + NUMBER_ACTIONS + counts),
+            Encoding::OneHot => Some(TOTALS + 2 + UP_CARDS + NUMBER_ACTIONS + counts),
+        }
+    }
+}
+
+impl TwentyOne {
+    pub(super) fn get_observation(&self) -> Observation {
+        match self.encoding {
+            Encoding::Legacy => Observation::Legacy(self.legacy_observation()),
+            encoding => Observation::Vector(self.vector_observation(encoding)),
+        }
+    }
+
+    fn legacy_observation(&self) -> ObservationVals {
+        let mut res: ObservationVals = [[[0; 3]; 3]; 3];
+        let value = self.players_value[1][self.players_active_hand[1]];
+        let vals: [u8; 2] = [
+            value.total,
+            self.players_hand[0][0].first().copied().unwrap_or(0),
+        ];
+        for i in 0..2 {
+            for j in 0..3 {
+                for k in 0..3 {
+                    res[i][j][k] = vals[i];
+                }
+            }
+        }
+        for (hand, value) in self.players_value[1].iter().take(6).enumerate() {
+            res[2][hand / 3][hand % 3] = value.total;
+        }
+        res[2][2] = [
+            value.is_soft as u8,
+            value.is_blackjack as u8,
+            value.is_bust() as u8,
+        ];
+        res
+    }
+
+    fn vector_observation(&self, encoding: Encoding) -> Vec<f32> {
+        let active = self.players_active_hand[1];
+        let value = self.players_value[1][active];
+        let hand = &self.players_hand[1][active];
+        let pair = hand.len() == 2 && card_points(hand[0]) == card_points(hand[1]);
+        let up_card = self.players_hand[0][0]
+            .first()
+            .map_or(0, |&card| card_points(card));
+        let mut observation = Vec::new();
+        if encoding == Encoding::OneHot {
+            let mut totals = [0.0; TOTALS];
+            totals[(value.total as usize).min(TOTALS - 1)] = 1.0;
+            observation.extend_from_slice(&totals);
+        } else {
+            observation.push(value.total as f32);
+        }
+        observation.push(value.is_soft as u8 as f32);
+        observation.push(pair as u8 as f32);
+        if encoding == Encoding::OneHot {
+            let mut up_cards = [0.0; UP_CARDS];
+            up_cards[up_card as usize] = 1.0;
+            observation.extend_from_slice(&up_cards);
+        } else {
+            observation.push(up_card as f32);
+        }
+        observation.extend(
+            self.legal_actions_of(1)
+                .iter()
+                .map(|&legal| legal as u8 as f32),
+        );
+        if self.counts {
+            let (running_count, remaining) = self.seen_counts();
+            let cards: usize = remaining.iter().sum();
+            let decks = cards as f32 / DECK.len() as f32;
+            let true_count = if cards > 0 {
+                running_count as f32 / decks
+            } else {
+                0.0
+            };
+            observation.push(running_count as f32);
+            observation.push(true_count);
+            observation.extend(remaining.iter().map(|&count| count as f32));
+        }
+        observation
+    }
+
+    // the running count and the cards left of each rank as the players see
+    // them, the dealer's hole card still being unknown
+    fn seen_counts(&self) -> (i32, [usize; RANKS]) {
+        let mut running_count = self.shoe.running_count();
+        let mut remaining = self.shoe.remaining_counts();
+        if let Some(card) = self.hole_card() {
+            running_count -= hi_lo(card);
+            remaining[rank_index(card)] += 1;
+        }
+        (running_count, remaining)
+    }
+
+    fn hole_card(&self) -> Option<Card> {
+        match self.players_hand[0][0].get(1) {
+            Some(&card) if !self.all_done() => Some(card),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::{BlackjackRules, HandValue, ACE};
+    use super::*;
+
+    fn vector(observation: Observation) -> Vec<f32> {
+        match observation {
+            Observation::Vector(vector) => vector,
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn features_describe_the_hand_and_the_actions() {
+        let mut game = TwentyOne::new(1, BlackjackRules::default());
+        game.set_observation(Encoding::Features, false);
+        let observation = vector(game.reset());
+        assert_eq!(Encoding::Features.len(false), Some(observation.len()));
+        // only the bets before the deal
+        assert_eq!(vec![0.0; 4], observation[..4].to_vec());
+        assert_eq!(8.0, observation[4..].iter().sum::<f32>());
+        game.step(4, true);
+        game.players_hand[1][0] = vec![8, 8];
+        game.players_value[1][0] = HandValue::of(&[8, 8]);
+        game.players_hand[0][0] = vec![ACE, 5];
+        let observation = vector(game.get_observation());
+        assert_eq!(vec![16.0, 0.0, 1.0, 11.0], observation[..4].to_vec());
+        // stand, hit, double, split, insurance and surrender
+        let actions: Vec<usize> = (0..NUMBER_ACTIONS)
+            .filter(|&action| observation[4 + action] == 1.0)
+            .collect();
+        assert_eq!(vec![0, 1, 2, 3, 12, 14], actions);
+    }
+
+    #[test]
+    fn counts_leave_the_hole_card_out() {
+        let mut game = TwentyOne::new(1, BlackjackRules::default());
+        game.set_observation(Encoding::OneHot, true);
+        game.reset();
+        let (observation, _, _) = game.step(4, true);
+        let observation = vector(observation);
+        assert_eq!(Encoding::OneHot.len(true), Some(observation.len()));
+        let seen = [
+            game.players_hand[1][0][0],
+            game.players_hand[1][0][1],
+            game.players_hand[0][0][0],
+        ];
+        let running_count: i32 = seen.iter().map(|&card| hi_lo(card)).sum();
+        let counts = &observation[observation.len() - 2 - RANKS..];
+        assert_eq!(running_count as f32, counts[0]);
+        let remaining: f32 = counts[2..].iter().sum();
+        assert_eq!((game.shoe.remaining() + 1) as f32, remaining);
+        assert_eq!(49.0, remaining);
+        // the total and the up card are one-hot
+        assert_eq!(1.0, observation[..TOTALS].iter().sum::<f32>());
+        assert_eq!(
+            1.0,
+            observation[TOTALS + 2..TOTALS + 2 + UP_CARDS]
+                .iter()
+                .sum::<f32>()
+        );
+    }
+}
@@ -1,11 +1,30 @@
 // The python library
 // use pyo3::{exceptions::PyException, types::{PyTuple, PyDict}};
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
+use pyo3::types::PyDict;
 
-use super::TwentyOne;
+use super::{
+    BlackjackRules, DoubleOn, Encoding, Observation, Shoe, Surrender, TwentyOne, MAX_DECKS,
+    NUMBER_ACTIONS,
+};
 
 pub type Card = u8;
 pub type Hand = Vec<Card>;
+/// total, soft, blackjack, bust
+pub type Value = (u8, bool, bool, bool);
+pub type State = (
+    Vec<Vec<Hand>>,
+    Vec<Vec<Value>>,
+    Vec<Vec<u64>>,
+    Vec<Vec<bool>>,
+    Vec<Vec<bool>>,
+    u8,
+    Vec<usize>,
+    Vec<bool>,
+    Vec<bool>,
+    Vec<bool>,
+);
 
 // PYTHON MODULE
 // ---------------------------------------------------------
@@ -17,11 +36,66 @@ pub struct BlackjackEngine {
 
 #[pymethods]
 impl BlackjackEngine {
+    /// Deals from a shoe of `decks` decks, shuffled once `penetration` of
+    /// it is dealt when it is `persistent` across rounds and before every
+    /// round otherwise. `rules` is a dict of table rules, see
+    /// `rules_from_dict`. Observations are the "legacy" 3x3x3 array unless
+    /// encoded as "features" or "one_hot", the vectors ending with the card
+    /// counts when `counts` is set.
     #[new]
-    fn new(n_players: usize) -> Self {
-        BlackjackEngine {
-            game: TwentyOne::new(n_players),
+    #[args(
+        decks = 1,
+        penetration = "1.0",
+        persistent = "false",
+        rules = "None",
+        observation = "\"legacy\"",
+        counts = "false"
+    )]
+    fn new(
+        n_players: usize,
+        decks: usize,
+        penetration: f32,
+        persistent: bool,
+        rules: Option<&PyDict>,
+        observation: &str,
+        counts: bool,
+    ) -> PyResult<Self> {
+        if decks == 0 || decks > MAX_DECKS {
+            return Err(PyValueError::new_err(format!(
+                "decks must be between 1 and {}, got {}",
+                MAX_DECKS, decks
+            )));
         }
+        if !(penetration > 0.0 && penetration <= 1.0) {
+            return Err(PyValueError::new_err(format!(
+                "penetration must be in (0, 1], got {}",
+                penetration
+            )));
+        }
+        let rules = match rules {
+            Some(dict) => rules_from_dict(dict)?,
+            None => BlackjackRules::default(),
+        };
+        let encoding = match observation {
+            "features" => Encoding::Features,
+            "one_hot" => Encoding::OneHot,
+            "legacy" => Encoding::Legacy,
+            other => {
+                return Err(PyValueError::new_err(format!(
+                    "unknown observation '{}', expected 'features', 'one_hot' or 'legacy'",
+                    other
+                )))
+            }
+        };
+        if counts && encoding == Encoding::Legacy {
+            return Err(PyValueError::new_err(
+                "the legacy observation has no room for the counts",
+            ));
+        }
+        let mut game = TwentyOne::new(n_players, rules);
+        game.set_shoe(Shoe::new(decks, penetration, persistent));
+        game.set_observation(encoding, counts);
+        Ok(BlackjackEngine { game })
     }
 
     pub fn legal_actions(&self) -> PyResult<Vec<u8>> {
@@ -33,12 +107,15 @@ impl BlackjackEngine {
         // 0 = stand
         // 1 = HIT
         // 2 = double down
-        // 3 = pull apart (currently disabled)
+        // 3 = split a pair into two hands
+        // 12 = insurance, when the dealer shows an ace
+        // 13 = even money, for a blackjack when the dealer shows an ace
+        // 14 = surrender, before splitting or drawing
         // example [true, true, true, false] hit, stand and doble down available
 
         let _legal_actions = self.game.legal_actions();
         let mut py_legal_actions = vec![];
-        for i in 0..12 {
+        for i in 0..NUMBER_ACTIONS {
             if _legal_actions[i] {
                 py_legal_actions.push(i as u8);
             }
@@ -46,16 +123,28 @@ impl BlackjackEngine {
         Ok(py_legal_actions)
     }
 
-    pub fn step(&mut self, action: u8) -> PyResult<(Vec<Vec<Vec<u8>>>, i64, bool)> {
+    /// Plays an action, raising `ValueError` when it is not one of
+    /// `legal_actions`
+    pub fn step(&mut self, py: Python, action: u8) -> PyResult<(PyObject, f64, bool)> {
+        if action as usize >= NUMBER_ACTIONS || !self.game.legal_actions()[action as usize] {
+            return Err(PyValueError::new_err(format!(
+                "action {} is not legal",
+                action
+            )));
+        }
         // a = observation
         // b = reward
         // c = done
         let (_a, b, c) = self.game.step(action, true);
-        let a = array_to_vector(_a);
+        let a = observation_to_py(py, _a);
         Ok((a, b[1], c))
     }
 
-    pub fn get_state(&self) -> PyResult<(Vec<Hand>, Vec<u8>, Vec<u64>, Vec<bool>, Vec<bool>, u8)> {
+    /// Returns the hands, values as `(total, soft, blackjack, bust)`, bets,
+    /// planted and busted flags of every hand of every player, the dealer
+    /// first, the current player and the hand each player is playing, and
+    /// whether each player took insurance, even money or surrendered
+    pub fn get_state(&self) -> PyResult<State> {
         let (
             players_hand,
             players_value,
@@ -63,17 +152,53 @@ impl BlackjackEngine {
             players_planted,
             players_busted,
             current_player,
+            players_active_hand,
+            players_insured,
+            players_even_money,
+            players_surrendered,
         ) = self.game.get_state();
         Ok((
             players_hand.clone(),
-            players_value.clone(),
+            players_value
+                .iter()
+                .map(|values| {
+                    values
+                        .iter()
+                        .map(|value| {
+                            (
+                                value.total,
+                                value.is_soft,
+                                value.is_blackjack,
+                                value.is_bust(),
+                            )
+                        })
+                        .collect()
+                })
+                .collect(),
             players_bet.clone(),
             players_planted.clone(),
             players_busted.clone(),
             current_player,
+            players_active_hand.clone(),
+            players_insured.clone(),
+            players_even_money.clone(),
+            players_surrendered.clone(),
         ))
     }
 
+    /// Returns the cards in the full shoe, the cards left to deal and
+    /// whether the cut card came out, so that the shoe is shuffled before
+    /// the next round
+    pub fn get_shoe(&self) -> PyResult<(usize, usize, bool)> {
+        let shoe = self.game.shoe();
+        Ok((shoe.size(), shoe.remaining(), shoe.cut_card_reached()))
+    }
+
+    /// Length of the observation vectors, `None` for the legacy array
+    pub fn observation_size(&self) -> PyResult<Option<usize>> {
+        Ok(self.game.observation_size())
+    }
+
     pub fn get_total_players(&self) -> PyResult<u8> {
         Ok(self.game.get_total_players())
     }
@@ -82,13 +207,111 @@ impl BlackjackEngine {
         Ok(self.game.to_play())
     }
 
-    pub fn reset(&mut self) -> PyResult<Vec<Vec<Vec<u8>>>> {
-        let res = array_to_vector(self.game.reset());
+    pub fn reset(&mut self, py: Python) -> PyResult<PyObject> {
+        let res = observation_to_py(py, self.game.reset());
         Ok(res)
     }
 }
 
+/// The keys `rules_from_dict` reads
+const RULE_KEYS: [&str; 8] = [
+    "dealer_hits_soft_17",
+    "blackjack_pays",
+    "double_after_split",
+    "double_on",
+    "bets",
+    "dealer_peeks",
+    "surrender",
+    "max_hands",
+];
+
+/// Builds table rules from a dict such as
+/// `{"dealer_hits_soft_17": True, "blackjack_pays": 1.2, "surrender": "none"}`.
+/// Missing keys keep their default value, unknown keys are refused.
+pub fn rules_from_dict(dict: &PyDict) -> PyResult<BlackjackRules> {
+    for key in dict.keys() {
+        let key: &str = key.extract()?;
+        if !RULE_KEYS.contains(&key) {
+            return Err(PyValueError::new_err(format!(
+                "unknown rule '{}', expected one of {}",
+                key,
+                RULE_KEYS.join(", ")
+            )));
+        }
+    }
+    let mut rules = BlackjackRules::default();
+    if let Some(value) = dict.get_item("dealer_hits_soft_17") {
+        rules.dealer_hits_soft_17 = value.extract()?;
+    }
+    if let Some(value) = dict.get_item("blackjack_pays") {
+        rules.blackjack_pays = value.extract()?;
+        if !(rules.blackjack_pays > 0.0 && rules.blackjack_pays.is_finite()) {
+            return Err(PyValueError::new_err(format!(
+                "blackjack_pays must be a positive number, got {}",
+                rules.blackjack_pays
+            )));
+        }
+    }
+    if let Some(value) = dict.get_item("double_after_split") {
+        rules.double_after_split = value.extract()?;
+    }
+    if let Some(value) = dict.get_item("double_on") {
+        rules.double_on = match value.extract::<&str>()? {
+            "any" => DoubleOn::AnyTwo,
+            "9-11" => DoubleOn::NineToEleven,
+            "10-11" => DoubleOn::TenToEleven,
+            other => {
+                return Err(PyValueError::new_err(format!(
+                    "unknown double_on '{}', expected 'any', '9-11' or '10-11'",
+                    other
+                )))
+            }
+        };
+    }
+    if let Some(value) = dict.get_item("bets") {
+        let bets: Vec<u64> = value.extract()?;
+        if bets.len() != rules.bets.len() || bets.contains(&0) {
+            return Err(PyValueError::new_err(format!(
+                "bets must be {} positive amounts, got {:?}",
+                rules.bets.len(),
+                bets
+            )));
+        }
+        rules.bets.copy_from_slice(&bets);
+    }
+    if let Some(value) = dict.get_item("dealer_peeks") {
+        rules.dealer_peeks = value.extract()?;
+    }
+    if let Some(value) = dict.get_item("surrender") {
+        rules.surrender = match value.extract::<&str>()? {
+            "late" => Surrender::Late,
+            "early" => Surrender::Early,
+            "none" => Surrender::NotAllowed,
+            other => {
+                return Err(PyValueError::new_err(format!(
+                    "unknown surrender '{}', expected 'late', 'early' or 'none'",
+                    other
+                )))
+            }
+        };
+    }
+    if let Some(value) = dict.get_item("max_hands") {
+        rules.max_hands = value.extract()?;
+        if rules.max_hands == 0 {
+            return Err(PyValueError::new_err("max_hands must be at least 1"));
+        }
+    }
+    Ok(rules)
+}
+
 // conversion functions
+pub fn observation_to_py(py: Python, observation: Observation) -> PyObject {
+    match observation {
+        Observation::Legacy(planes) => array_to_vector(planes).into_py(py),
+        Observation::Vector(vector) => vector.into_py(py),
+    }
+}
+
 pub fn array_to_vector(_a: [[[u8; 3]; 3]; 3]) -> Vec<Vec<Vec<u8>>> {
     let mut a = Vec::new();
     for i in 0..3 {
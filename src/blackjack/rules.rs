@@ -0,0 +1,69 @@
+// Table rules
+// ---------------------------------------------------------
+// What changes from one blackjack table to another: how the dealer plays
+// soft 17, what a blackjack pays, when doubling, splitting and surrendering
+// are allowed and the amounts of the bet actions.
+
+/// When a player may give up half their bet instead of playing the hand
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Surrender {
+    NotAllowed,
+    /// Only once the dealer checked for blackjack: against a dealer
+    /// blackjack the whole bet is lost
+    Late,
+    /// Before the dealer checks for blackjack, half the bet being lost
+    /// whatever the dealer holds
+    Early,
+}
+
+/// Two card totals a player may double down on
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DoubleOn {
+    AnyTwo,
+    NineToEleven,
+    TenToEleven,
+}
+
+impl DoubleOn {
+    pub fn allows(&self, total: u8) -> bool {
+        match self {
+            DoubleOn::AnyTwo => true,
+            DoubleOn::NineToEleven => (9..=11).contains(&total),
+            DoubleOn::TenToEleven => (10..=11).contains(&total),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BlackjackRules {
+    /// The dealer draws to a soft 17 (H17) instead of standing (S17)
+    pub dealer_hits_soft_17: bool,
+    /// Winnings of a blackjack for each unit bet, 1.5 for 3:2 and 1.2 for
+    /// 6:5
+    pub blackjack_pays: f64,
+    pub double_after_split: bool,
+    pub double_on: DoubleOn,
+    /// Amounts of the bet actions 4 to 11
+    pub bets: [u64; 8],
+    /// The dealer checks the hole card for blackjack before the players
+    /// play, so that against a blackjack they only lose their original bet
+    pub dealer_peeks: bool,
+    pub surrender: Surrender,
+    /// Hands a player can hold by splitting and resplitting
+    pub max_hands: usize,
+}
+
+impl Default for BlackjackRules {
+    fn default() -> BlackjackRules {
+        BlackjackRules {
+            dealer_hits_soft_17: false,
+            blackjack_pays: 1.5,
+            double_after_split: true,
+            double_on: DoubleOn::AnyTwo,
+            bets: [1, 5, 10, 25, 50, 100, 500, 1000],
+            dealer_peeks: true,
+            surrender: Surrender::Late,
+            max_hands: 4,
+        }
+    }
+}
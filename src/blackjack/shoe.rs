@@ -0,0 +1,265 @@
+// Blackjack shoe
+// ---------------------------------------------------------
+// One to eight decks shuffled together and dealt from the top. A cut card
+// placed at `penetration` of the shoe marks where it gets reshuffled: the
+// round being played goes on, and the shoe is shuffled before the next one.
+// A shoe that is not persistent is shuffled before every round, every
+// round starting from full decks. A shoe running out in the middle of a
+// round is refilled with the discards, the cards on the table staying out
+// of it; only when the table holds every card of the shoe is a fresh shoe
+// brought in, which then has copies of the cards on the table until the
+// next round. The shoe keeps the Hi-Lo running count of the cards dealt
+// since it was last shuffled, and of the cards on the table after a refill.
+
+use rand::seq::SliceRandom;
+
+use super::{Card, Deck, ACE, DECK};
+
+pub const MAX_DECKS: usize = 8;
+// 2 to 9, the ten valued cards and the ace
+pub const RANKS: usize = 10;
+
+#[derive(Debug, Clone)]
+pub struct Shoe {
+    decks: usize,
+    penetration: f32,
+    persistent: bool,
+    // the top of the shoe is the end of the vector
+    cards: Deck,
+    // dealt since the round started
+    on_table: Deck,
+    running_count: i32,
+}
+
+impl Shoe {
+    /// A shuffled shoe of `decks` decks, reshuffled once a `penetration`
+    /// fraction of it is dealt. `decks` is clamped to 1..=8 and
+    /// `penetration` to (0, 1].
+    pub fn new(decks: usize, penetration: f32, persistent: bool) -> Shoe {
+        let mut shoe = Shoe {
+            decks: decks.clamp(1, MAX_DECKS),
+            penetration: if penetration > 0.0 {
+                penetration.min(1.0)
+            } else {
+                1.0
+            },
+            persistent,
+            cards: Vec::new(),
+            on_table: Vec::new(),
+            running_count: 0,
+        };
+        shoe.shuffle();
+        shoe
+    }
+
+    /// Cards in the full shoe
+    pub fn size(&self) -> usize {
+        self.decks * DECK.len()
+    }
+
+    /// Cards left to deal
+    pub fn remaining(&self) -> usize {
+        self.cards.len()
+    }
+
+    pub fn dealt(&self) -> usize {
+        self.size() - self.remaining()
+    }
+
+    /// Whether the cut card came out
+    pub fn cut_card_reached(&self) -> bool {
+        self.dealt() as f32 >= self.penetration * self.size() as f32
+    }
+
+    /// Hi-Lo count of the cards dealt since the last shuffle
+    pub fn running_count(&self) -> i32 {
+        self.running_count
+    }
+
+    /// Cards left to deal of each rank, as indexed by `rank_index`
+    pub fn remaining_counts(&self) -> [usize; RANKS] {
+        let mut counts = [0; RANKS];
+        for &card in self.cards.iter() {
+            counts[rank_index(card)] += 1;
+        }
+        counts
+    }
+
+    /// Puts every card back and shuffles them
+    pub fn shuffle(&mut self) {
+        let mut rng = rand::thread_rng();
+        self.cards = DECK.repeat(self.decks);
+        self.cards.shuffle(&mut rng);
+        self.on_table.clear();
+        self.running_count = 0;
+    }
+
+    /// To call before a round: the cards of the last round are discarded,
+    /// and a shoe that is not persistent or whose cut card came out is
+    /// shuffled. Returns whether it was.
+    pub fn start_round(&mut self) -> bool {
+        self.on_table.clear();
+        if !self.persistent || self.cut_card_reached() {
+            self.shuffle();
+            return true;
+        }
+        false
+    }
+
+    /// The top card, the discards being shuffled back in when the shoe
+    /// runs out in the middle of a round
+    pub fn draw(&mut self) -> Card {
+        if self.cards.is_empty() {
+            self.shuffle_discards();
+        }
+        let card = self.cards.pop().unwrap();
+        self.running_count += hi_lo(card);
+        self.on_table.push(card);
+        card
+    }
+
+    // refills the shoe with every card but those on the table, counting
+    // them as dealt. The table keeps its cards when it holds the whole shoe,
+    // the fresh decks brought in overlapping them.
+    fn shuffle_discards(&mut self) {
+        let mut cards = DECK.repeat(self.decks);
+        for card in self.on_table.iter() {
+            // a table already refilled from fresh decks holds more cards
+            // than the shoe
+            if let Some(index) = cards.iter().position(|other| other == card) {
+                cards.swap_remove(index);
+            }
+        }
+        if cards.is_empty() {
+            cards = DECK.repeat(self.decks);
+        }
+        cards.shuffle(&mut rand::thread_rng());
+        self.cards = cards;
+        self.running_count = self.on_table.iter().map(|&card| hi_lo(card)).sum();
+    }
+}
+
+/// 0 to 7 for the cards 2 to 9, 8 for the ten valued cards and 9 for the ace
+pub fn rank_index(card: Card) -> usize {
+    match card {
+        ACE => 9,
+        10..=13 => 8,
+        _ => card as usize - 2,
+    }
+}
+
+/// Hi-Lo value of a card: +1 for 2 to 6, -1 for the tens and aces
+pub fn hi_lo(card: Card) -> i32 {
+    match card {
+        2..=6 => 1,
+        7..=9 => 0,
+        _ => -1,
+    }
+}
+
+impl Default for Shoe {
+    /// A single deck shuffled before every round
+    fn default() -> Shoe {
+        Shoe::new(1, 1.0, false)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn shoe_holds_every_card_of_its_decks() {
+        let mut shoe = Shoe::new(6, 0.75, true);
+        assert_eq!(312, shoe.size());
+        let mut counts = [0; 15];
+        for _ in 0..shoe.size() {
+            counts[shoe.draw() as usize] += 1;
+        }
+        assert_eq!(0, shoe.remaining());
+        assert!(counts[2..].iter().all(|&count| count == 24));
+        // a whole shoe counts back to zero
+        assert_eq!(0, shoe.running_count());
+        // an empty shoe is shuffled again
+        assert!(shoe.start_round());
+        shoe.draw();
+        assert_eq!(311, shoe.remaining());
+    }
+
+    #[test]
+    fn shoe_running_out_is_refilled_with_the_discards() {
+        let mut shoe = Shoe::new(1, 1.0, true);
+        for _ in 0..50 {
+            shoe.draw();
+        }
+        assert!(!shoe.start_round());
+        // the last two cards, then three of the discards
+        let table: Vec<Card> = (0..5).map(|_| shoe.draw()).collect();
+        assert_eq!(47, shoe.remaining());
+        let mut counts = shoe.remaining_counts();
+        for &card in table.iter() {
+            counts[rank_index(card)] += 1;
+        }
+        assert_eq!([4, 4, 4, 4, 4, 4, 4, 4, 16, 4], counts);
+        let table_count: i32 = table.iter().map(|&card| hi_lo(card)).sum();
+        assert_eq!(table_count, shoe.running_count());
+    }
+
+    #[test]
+    fn table_holding_the_whole_shoe_gets_fresh_decks() {
+        let mut shoe = Shoe::new(1, 1.0, true);
+        let mut table: Vec<Card> = (0..52).map(|_| shoe.draw()).collect();
+        // the table keeps its cards and the count goes on from them
+        table.push(shoe.draw());
+        assert_eq!(51, shoe.remaining());
+        let mut counts = shoe.remaining_counts();
+        counts[rank_index(table[52])] += 1;
+        assert_eq!([4, 4, 4, 4, 4, 4, 4, 4, 16, 4], counts);
+        assert_eq!(hi_lo(table[52]), shoe.running_count());
+        // and again once the fresh decks run out
+        for _ in 0..60 {
+            table.push(shoe.draw());
+        }
+        let table_count: i32 = table.iter().map(|&card| hi_lo(card)).sum();
+        assert_eq!(table_count, shoe.running_count());
+        assert_eq!(52 - 9, shoe.remaining());
+        // the next round goes on with the fresh decks
+        assert!(!shoe.start_round());
+        assert_eq!(52 - 9, shoe.remaining());
+        assert_eq!(table_count, shoe.running_count());
+    }
+
+    #[test]
+    fn persistent_shoe_is_reshuffled_after_the_cut_card() {
+        let mut shoe = Shoe::new(2, 0.5, true);
+        for _ in 0..51 {
+            shoe.draw();
+        }
+        assert!(!shoe.start_round());
+        assert_eq!(51, shoe.dealt());
+        shoe.draw();
+        assert!(shoe.cut_card_reached());
+        assert_eq!(
+            shoe.remaining_counts().iter().sum::<usize>(),
+            shoe.remaining()
+        );
+        assert!(shoe.start_round());
+        assert_eq!(104, shoe.remaining());
+        assert_eq!(0, shoe.running_count());
+        assert_eq!(32, shoe.remaining_counts()[8]);
+        let mut shoe = Shoe::default();
+        shoe.draw();
+        assert!(shoe.start_round());
+        assert_eq!(52, shoe.remaining());
+    }
+
+    #[test]
+    fn settings_are_kept_in_range() {
+        let shoe = Shoe::new(0, 0.0, false);
+        assert_eq!(1, shoe.decks);
+        assert_eq!(1.0, shoe.penetration);
+        let shoe = Shoe::new(20, 2.0, false);
+        assert_eq!(MAX_DECKS, shoe.decks);
+        assert_eq!(1.0, shoe.penetration);
+    }
+}
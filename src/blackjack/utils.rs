@@ -1 +1 @@
-// Where the utils file goes
\ No newline at end of file
+// Where the utils file goes
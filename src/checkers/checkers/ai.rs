@@ -1,5 +1,7 @@
 use super::board::{Board, BoardPosition};
+use super::piece::PieceType;
 use super::player::Player;
+use super::rules::Rules;
 use std::collections::HashSet;
 
 #[derive(Copy, Clone)]
@@ -96,6 +98,68 @@ impl JumpMove {
         }
     }
 
+    /// Like `contains_jump_sequence`, but the sequence must also end on a
+    /// leaf of the tree, i.e. no further jump is available after it.
+    pub fn contains_complete_jump_sequence(&self, jumps: &[BoardPosition]) -> bool {
+        match jumps.split_first() {
+            None => false,
+            Some((first, rest)) => {
+                if first.row != self.from_row || first.column != self.from_col {
+                    false
+                } else if rest.is_empty() {
+                    self.jumps.is_empty()
+                } else {
+                    self.jumps
+                        .iter()
+                        .any(|subtree| subtree.contains_complete_jump_sequence(rest))
+                }
+            }
+        }
+    }
+
+    /// Every path from the root to a leaf of the tree, i.e. every complete
+    /// capture sequence, starting with the position of the jumping piece.
+    pub fn sequences(&self) -> Vec<Vec<BoardPosition>> {
+        let position = BoardPosition::new(self.from_row, self.from_col);
+        if self.jumps.is_empty() {
+            return vec![vec![position]];
+        }
+        let mut sequences = Vec::new();
+        for subtree in self.jumps.iter() {
+            for mut sequence in subtree.sequences() {
+                sequence.insert(0, position);
+                sequences.push(sequence);
+            }
+        }
+        sequences
+    }
+
+    /// Builds the jump trees containing exactly the given sequences, one
+    /// tree for every distinct starting position.
+    pub fn from_sequences(sequences: &[Vec<BoardPosition>]) -> Vec<JumpMove> {
+        let mut trees = Vec::new();
+        for sequence in sequences {
+            JumpMove::insert_sequence(&mut trees, sequence);
+        }
+        trees
+    }
+
+    fn insert_sequence(trees: &mut Vec<JumpMove>, sequence: &[BoardPosition]) {
+        if let Some((first, rest)) = sequence.split_first() {
+            let index = match trees
+                .iter()
+                .position(|tree| tree.from_row == first.row && tree.from_col == first.column)
+            {
+                Some(index) => index,
+                None => {
+                    trees.push(JumpMove::new(first.row, first.column));
+                    trees.len() - 1
+                }
+            };
+            JumpMove::insert_sequence(&mut trees[index].jumps, rest);
+        }
+    }
+
     pub fn from_row(&self) -> usize {
         self.from_row
     }
@@ -392,6 +456,176 @@ fn push_jump_for_king_if_valid(
     curr_jump_root.jumps.push(jump);
 }
 
+const DIAGONALS: [(isize, isize); 4] = [(-1, -1), (-1, 1), (1, -1), (1, 1)];
+
+// the next tile along a diagonal, if it is still on the board
+fn diagonal_step(
+    board: &Board,
+    position: BoardPosition,
+    step: (isize, isize),
+) -> Option<BoardPosition> {
+    let row = position.row as isize + step.0;
+    let col = position.column as isize + step.1;
+    if row < 0
+        || col < 0
+        || row >= board.number_rows() as isize
+        || col >= board.number_columns() as isize
+    {
+        None
+    } else {
+        Some(BoardPosition::new(row as usize, col as usize))
+    }
+}
+
+/// Determines the capture sequences available to the piece at `row`, `col`
+/// under any set of `Rules`. Like `find_jump_moves_for_man`, every node of
+/// the returned tree is a tile the piece lands on.
+///
+/// Captured pieces stay on the board until the move is over, so they can
+/// neither be jumped twice nor be jumped through. A flying king that can go
+/// on capturing from some of its landing tiles has to land on one of them.
+pub fn find_jump_moves_with_rules(
+    board: &Board,
+    player: &Player,
+    direction: Direction,
+    piece_type: PieceType,
+    rules: &Rules,
+    row: usize,
+    col: usize,
+) -> JumpMove {
+    let mut jump_root = JumpMove::new(row, col);
+    let mut captured = HashSet::new();
+    let is_king = match piece_type {
+        PieceType::Man => false,
+        PieceType::King => true,
+    };
+
+    push_jumps_with_rules(
+        board,
+        player,
+        direction,
+        rules,
+        BoardPosition::new(row, col),
+        is_king,
+        &mut jump_root,
+        &mut captured,
+    );
+
+    jump_root
+}
+
+#[allow(clippy::too_many_arguments)]
+fn push_jumps_with_rules(
+    board: &Board,
+    player: &Player,
+    direction: Direction,
+    rules: &Rules,
+    init_position: BoardPosition,
+    is_king: bool,
+    curr_jump_root: &mut JumpMove,
+    captured: &mut HashSet<BoardPosition>,
+) {
+    let (forward, crown_row) = match direction {
+        Direction::IncreasingRank => (1, board.number_rows() - 1),
+        Direction::DecreasingRank => (-1, 0),
+    };
+    let flying = is_king && rules.flying_kings;
+    // the jumping piece "floats" around the board, so its initial tile is free
+    let is_free = |position: BoardPosition| {
+        position == init_position
+            || board
+                .get_tile(position.row, position.column)
+                .get_piece()
+                .is_none()
+    };
+    let start = BoardPosition::new(curr_jump_root.from_row, curr_jump_root.from_col);
+
+    for &step in DIAGONALS.iter() {
+        if !is_king && !rules.men_capture_backwards && step.0 != forward {
+            continue;
+        }
+
+        // find the piece to capture
+        let mut target = diagonal_step(board, start, step);
+        while flying && target.map(is_free).unwrap_or(false) {
+            target = diagonal_step(board, target.unwrap(), step);
+        }
+        let target = match target {
+            Some(target) if !is_free(target) && !captured.contains(&target) => target,
+            _ => continue,
+        };
+        let capturable = match board.get_tile(target.row, target.column).get_piece() {
+            Some(piece) => {
+                piece.get_player_id() != player.id
+                    && (is_king
+                        || rules.men_capture_kings
+                        || matches!(piece.get_type(), PieceType::Man))
+            }
+            None => false,
+        };
+        if !capturable {
+            continue;
+        }
+
+        // collect the tiles the piece may land on
+        let mut landings = Vec::new();
+        let mut landing = diagonal_step(board, target, step);
+        while let Some(position) = landing {
+            if !is_free(position) {
+                break;
+            }
+            landings.push(position);
+            if !flying {
+                break;
+            }
+            landing = diagonal_step(board, position, step);
+        }
+
+        captured.insert(target);
+        let mut branches = Vec::new();
+        for position in landings {
+            let crowned = !is_king && rules.crown_during_capture && position.row == crown_row;
+            let mut jump = JumpMove::new(position.row, position.column);
+            push_jumps_with_rules(
+                board,
+                player,
+                direction,
+                rules,
+                init_position,
+                is_king || crowned,
+                &mut jump,
+                captured,
+            );
+            branches.push(jump);
+        }
+        captured.remove(&target);
+
+        if flying && branches.iter().any(|branch| !branch.jumps.is_empty()) {
+            branches.retain(|branch| !branch.jumps.is_empty());
+        }
+        curr_jump_root.jumps.extend(branches);
+    }
+}
+
+/// Given the position of a flying king, determines the simple moves along
+/// every diagonal up to the first occupied tile.
+pub fn find_simple_moves_for_flying_king(board: &Board, row: usize, col: usize) -> Vec<SimpleMove> {
+    let mut moves = Vec::new();
+
+    for &step in DIAGONALS.iter() {
+        let mut position = diagonal_step(board, BoardPosition::new(row, col), step);
+        while let Some(to) = position {
+            if board.get_tile(to.row, to.column).get_piece().is_some() {
+                break;
+            }
+            moves.push(SimpleMove::new(row, col, to.row, to.column));
+            position = diagonal_step(board, to, step);
+        }
+    }
+
+    moves
+}
+
 fn get_row_offsets(direction: Direction) -> (TileOffset, TileOffset) {
     let (pwnd_row_offset, jump_row_offset) = match direction {
         Direction::DecreasingRank => (TileOffset::Negative(1), TileOffset::Negative(2)),
@@ -657,6 +891,54 @@ mod test {
             let result = jump_tree.contains_jump_sequence(&jumps);
             assert_eq!(true, result);
         }
+
+        #[test]
+        fn multi_jump_tree_incomplete_sequence() {
+            let jump_tree = JumpMove::with_jumps(
+                5,
+                5,
+                vec![JumpMove::with_jumps(3, 3, vec![JumpMove::new(1, 1)])],
+            );
+
+            let jumps = vec![BoardPosition::new(5, 5), BoardPosition::new(3, 3)];
+            let result = jump_tree.contains_complete_jump_sequence(&jumps);
+            assert_eq!(false, result);
+
+            let jumps = vec![
+                BoardPosition::new(5, 5),
+                BoardPosition::new(3, 3),
+                BoardPosition::new(1, 1),
+            ];
+            let result = jump_tree.contains_complete_jump_sequence(&jumps);
+            assert_eq!(true, result);
+        }
+
+        #[test]
+        fn branching_jump_tree_sequences() {
+            let jump_tree = JumpMove::with_jumps(
+                5,
+                5,
+                vec![
+                    JumpMove::with_jumps(3, 3, vec![JumpMove::new(1, 1), JumpMove::new(1, 5)]),
+                    JumpMove::new(7, 7),
+                ],
+            );
+
+            let exp_result = vec![
+                vec![
+                    BoardPosition::new(5, 5),
+                    BoardPosition::new(3, 3),
+                    BoardPosition::new(1, 1),
+                ],
+                vec![
+                    BoardPosition::new(5, 5),
+                    BoardPosition::new(3, 3),
+                    BoardPosition::new(1, 5),
+                ],
+                vec![BoardPosition::new(5, 5), BoardPosition::new(7, 7)],
+            ];
+            assert_eq!(exp_result, jump_tree.sequences());
+        }
     }
 
     mod simple_move {
@@ -0,0 +1,463 @@
+use super::{Board, BoardPosition, KingPiece, ManPiece, OccupiedTile, PieceType, Player};
+
+// Bitboards
+// ---------------------------------------------------------
+// The 32 dark squares of the 8x8 board are numbered row by row from player
+// 1's back row, `square = row * 4 + column / 2`, the same numbering used by
+// the checkers actions. Bit `s` of a mask is set when square `s` holds a
+// piece. Only English draughts rules are supported.
+
+/// Number of playable squares on the board
+pub const NUMBER_SQUARES: usize = 32;
+
+const BOARD_SIZE: isize = 8;
+
+/// Row and column steps of the four diagonals. The first two are the
+/// forward directions of player 1, the last two those of player 2.
+const DIRECTIONS: [(isize, isize); 4] = [(1, -1), (1, 1), (-1, -1), (-1, 1)];
+
+/// The square `distance` steps away along each diagonal, if any
+const fn diagonal_table(distance: isize) -> [[Option<u8>; 4]; NUMBER_SQUARES] {
+    let mut table = [[None; 4]; NUMBER_SQUARES];
+    let mut square = 0;
+    while square < NUMBER_SQUARES {
+        let row = (square / 4) as isize;
+        let column = ((square % 4) * 2 + (square / 4) % 2) as isize;
+        let mut direction = 0;
+        while direction < 4 {
+            let r = row + DIRECTIONS[direction].0 * distance;
+            let c = column + DIRECTIONS[direction].1 * distance;
+            if r >= 0 && r < BOARD_SIZE && c >= 0 && c < BOARD_SIZE {
+                table[square][direction] = Some((r * 4 + c / 2) as u8);
+            }
+            direction += 1;
+        }
+        square += 1;
+    }
+    table
+}
+
+const NEIGHBOURS: [[Option<u8>; 4]; NUMBER_SQUARES] = diagonal_table(1);
+const JUMPS: [[Option<u8>; 4]; NUMBER_SQUARES] = diagonal_table(2);
+
+/// Rows where the men of each player are crowned
+pub const CROWN_ROWS: [u32; 2] = [0xF000_0000, 0x0000_000F];
+
+pub fn square_to_position(square: usize) -> BoardPosition {
+    let row = square / 4;
+    BoardPosition::new(row, (square % 4) * 2 + row % 2)
+}
+
+pub fn position_to_square(position: &BoardPosition) -> usize {
+    position.row * 4 + position.column / 2
+}
+
+fn bit(square: u8) -> u32 {
+    1 << square
+}
+
+fn squares(mut mask: u32) -> impl Iterator<Item = u8> {
+    std::iter::from_fn(move || {
+        if mask == 0 {
+            None
+        } else {
+            let square = mask.trailing_zeros() as u8;
+            mask &= mask - 1;
+            Some(square)
+        }
+    })
+}
+
+/// A move given as the squares visited by the moving piece, together with
+/// the squares of the pieces it captures.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BitMove {
+    pub path: Vec<u8>,
+    pub captured: u32,
+}
+
+impl BitMove {
+    pub fn from(&self) -> u8 {
+        self.path[0]
+    }
+
+    pub fn to(&self) -> u8 {
+        self.path[self.path.len() - 1]
+    }
+
+    pub fn is_capture(&self) -> bool {
+        self.captured != 0
+    }
+}
+
+/// A compact checkers position: the men and kings of each player as 32 bit
+/// masks plus the index of the player to move. Unlike `Game` it is `Copy`
+/// and hashable, which makes it cheap to use in tree searches.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Position {
+    men: [u32; 2],
+    kings: [u32; 2],
+    to_move: usize,
+}
+
+impl Position {
+    /// The starting position, player 1 to move
+    pub fn new() -> Position {
+        Position {
+            men: [0x0000_0FFF, 0xFFF0_0000],
+            kings: [0, 0],
+            to_move: 0,
+        }
+    }
+
+    pub fn from_masks(men: [u32; 2], kings: [u32; 2], to_move: usize) -> Position {
+        assert_eq!(0, (men[0] | kings[0]) & (men[1] | kings[1]));
+        assert_eq!(0, men[0] & kings[0]);
+        assert_eq!(0, men[1] & kings[1]);
+        Position {
+            men,
+            kings,
+            to_move,
+        }
+    }
+
+    /// Reads an 8x8 board, `to_move` being the index of the player to move
+    /// (0 for player 1, 1 for player 2).
+    pub fn from_board(board: &Board, to_move: usize) -> Position {
+        assert_eq!(8, board.number_rows());
+        assert_eq!(8, board.number_columns());
+        let mut position = Position {
+            men: [0, 0],
+            kings: [0, 0],
+            to_move,
+        };
+        for square in 0..NUMBER_SQUARES {
+            let BoardPosition { row, column } = square_to_position(square);
+            if let Some(piece) = board.get_tile(row, column).get_piece() {
+                let player = (piece.get_player_id() - 1) as usize;
+                match piece.get_type() {
+                    PieceType::Man => position.men[player] |= bit(square as u8),
+                    PieceType::King => position.kings[player] |= bit(square as u8),
+                }
+            }
+        }
+        position
+    }
+
+    /// Builds the equivalent 8x8 board
+    pub fn to_board(self) -> Board {
+        let mut board = Board::new(8, 8);
+        for square in 0..NUMBER_SQUARES {
+            if let Some((player, piece_type)) = self.piece_at(square) {
+                let player = Player {
+                    id: player as u32 + 1,
+                };
+                let tile = match piece_type {
+                    PieceType::Man => OccupiedTile::new(Box::new(ManPiece::new(&player))),
+                    PieceType::King => OccupiedTile::new(Box::new(KingPiece::new(&player))),
+                };
+                let BoardPosition { row, column } = square_to_position(square);
+                board.set_tile(row, column, Box::new(tile));
+            }
+        }
+        board
+    }
+
+    pub fn to_move(self) -> usize {
+        self.to_move
+    }
+
+    /// The same pieces with `player` to move
+    pub fn with_to_move(&self, player: usize) -> Position {
+        Position {
+            to_move: player,
+            ..*self
+        }
+    }
+
+    pub fn men(&self, player: usize) -> u32 {
+        self.men[player]
+    }
+
+    pub fn kings(&self, player: usize) -> u32 {
+        self.kings[player]
+    }
+
+    pub fn pieces(&self, player: usize) -> u32 {
+        self.men[player] | self.kings[player]
+    }
+
+    pub fn occupied(&self) -> u32 {
+        self.pieces(0) | self.pieces(1)
+    }
+
+    /// The piece on `square` as `(player, piece type)`
+    pub fn piece_at(&self, square: usize) -> Option<(usize, PieceType)> {
+        let mask = bit(square as u8);
+        (0..2).find_map(|player| {
+            if self.men[player] & mask != 0 {
+                Some((player, PieceType::Man))
+            } else if self.kings[player] & mask != 0 {
+                Some((player, PieceType::King))
+            } else {
+                None
+            }
+        })
+    }
+
+    fn directions(&self, square: u8) -> &'static [usize] {
+        if self.kings[self.to_move] & bit(square) != 0 {
+            &[0, 1, 2, 3]
+        } else if self.to_move == 0 {
+            &[0, 1]
+        } else {
+            &[2, 3]
+        }
+    }
+
+    /// Moves to an adjacent empty square, equivalent to
+    /// `find_simple_moves_for_man` and `find_simple_moves_for_king`
+    pub fn simple_moves(&self) -> Vec<BitMove> {
+        let empty = !self.occupied();
+        let mut moves = Vec::new();
+        for from in squares(self.pieces(self.to_move)) {
+            for &direction in self.directions(from) {
+                if let Some(to) = NEIGHBOURS[from as usize][direction] {
+                    if empty & bit(to) != 0 {
+                        moves.push(BitMove {
+                            path: vec![from, to],
+                            captured: 0,
+                        });
+                    }
+                }
+            }
+        }
+        moves
+    }
+
+    /// Every complete capture sequence, equivalent to the sequences of
+    /// `find_jump_moves_for_man` and `find_jump_moves_for_king`
+    pub fn jump_moves(&self) -> Vec<BitMove> {
+        let mut moves = Vec::new();
+        for from in squares(self.pieces(self.to_move)) {
+            // the jumping piece leaves its square, so it may land there again
+            let empty = !(self.occupied() & !bit(from));
+            let mut path = vec![from];
+            self.push_jumps(self.directions(from), empty, 0, &mut path, &mut moves);
+        }
+        moves
+    }
+
+    fn push_jumps(
+        &self,
+        directions: &[usize],
+        empty: u32,
+        captured: u32,
+        path: &mut Vec<u8>,
+        moves: &mut Vec<BitMove>,
+    ) {
+        let from = path[path.len() - 1] as usize;
+        let enemies = self.pieces(1 - self.to_move) & !captured;
+        let mut extended = false;
+        for &direction in directions {
+            let (over, to) = match (NEIGHBOURS[from][direction], JUMPS[from][direction]) {
+                (Some(over), Some(to)) => (over, to),
+                _ => continue,
+            };
+            if enemies & bit(over) == 0 || empty & bit(to) == 0 {
+                continue;
+            }
+            extended = true;
+            path.push(to);
+            self.push_jumps(directions, empty, captured | bit(over), path, moves);
+            path.pop();
+        }
+        if !extended && path.len() > 1 {
+            moves.push(BitMove {
+                path: path.clone(),
+                captured,
+            });
+        }
+    }
+
+    /// The captures if there are any, as jumping is mandatory, otherwise
+    /// the simple moves
+    pub fn legal_moves(&self) -> Vec<BitMove> {
+        let jump_moves = self.jump_moves();
+        if jump_moves.is_empty() {
+            self.simple_moves()
+        } else {
+            jump_moves
+        }
+    }
+
+    /// The player to move has lost when they cannot move
+    pub fn is_game_over(&self) -> bool {
+        self.legal_moves().is_empty()
+    }
+
+    /// Plays a legal move, crowning men that reach the last row, and hands
+    /// the turn to the other player.
+    pub fn apply(&mut self, the_move: &BitMove) {
+        let me = self.to_move;
+        let from = bit(the_move.from());
+        let to = bit(the_move.to());
+        if self.kings[me] & from != 0 {
+            self.kings[me] = (self.kings[me] & !from) | to;
+        } else if to & CROWN_ROWS[me] != 0 {
+            self.men[me] &= !from;
+            self.kings[me] |= to;
+        } else {
+            self.men[me] = (self.men[me] & !from) | to;
+        }
+        let opponent = 1 - me;
+        self.men[opponent] &= !the_move.captured;
+        self.kings[opponent] &= !the_move.captured;
+        self.to_move = opponent;
+    }
+
+    /// The position after playing `the_move`
+    pub fn after(&self, the_move: &BitMove) -> Position {
+        let mut position = *self;
+        position.apply(the_move);
+        position
+    }
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Position::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::Game;
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::seq::SliceRandom;
+    use rand::SeedableRng;
+
+    fn game_moves(game: &Game) -> Vec<Vec<u8>> {
+        let to_square = |position: &BoardPosition| position_to_square(position) as u8;
+        let jump_moves = game.get_available_jump_moves();
+        let mut moves: Vec<Vec<u8>> = if jump_moves.is_empty() {
+            game.get_available_simple_moves()
+                .iter()
+                .map(|simple_move| {
+                    vec![
+                        to_square(&BoardPosition::new(
+                            simple_move.from_row(),
+                            simple_move.from_column(),
+                        )),
+                        to_square(&BoardPosition::new(
+                            simple_move.to_row(),
+                            simple_move.to_column(),
+                        )),
+                    ]
+                })
+                .collect()
+        } else {
+            jump_moves
+                .iter()
+                .flat_map(|jump_move| jump_move.sequences())
+                .map(|sequence| sequence.iter().map(to_square).collect())
+                .collect()
+        };
+        moves.sort();
+        moves
+    }
+
+    fn position_moves(position: &Position) -> Vec<Vec<u8>> {
+        let mut moves: Vec<Vec<u8>> = position
+            .legal_moves()
+            .into_iter()
+            .map(|the_move| the_move.path)
+            .collect();
+        moves.sort();
+        moves
+    }
+
+    #[test]
+    fn initial_position_matches_board() {
+        let game = Game::new();
+        assert_eq!(Position::new(), Position::from_board(game.board(), 0));
+        assert_eq!(7, Position::new().legal_moves().len());
+    }
+
+    #[test]
+    fn board_round_trip() {
+        let position = Position::from_masks([bit(0) | bit(9), bit(30)], [bit(20), bit(3)], 1);
+        assert_eq!(position, Position::from_board(&position.to_board(), 1));
+    }
+
+    #[test]
+    fn king_jumps_back_to_its_square() {
+        // a king on 9 surrounded by four enemy men can come back to 9
+        let position =
+            Position::from_masks([0, bit(13) | bit(14) | bit(5) | bit(6)], [bit(9), 0], 0);
+        let moves = position.jump_moves();
+        assert!(moves.iter().any(|the_move| the_move.path.len() == 5
+            && the_move.from() == 9
+            && the_move.to() == 9));
+        assert!(moves
+            .iter()
+            .all(|the_move| the_move.captured.count_ones() as usize == the_move.path.len() - 1));
+    }
+
+    #[test]
+    fn crowning_ends_the_move() {
+        let mut position = Position::from_masks([bit(25), bit(2)], [0, 0], 0);
+        let the_move = BitMove {
+            path: vec![25, 29],
+            captured: 0,
+        };
+        assert!(position.legal_moves().contains(&the_move));
+        position.apply(&the_move);
+        assert_eq!(bit(29), position.kings(0));
+        assert_eq!(0, position.men(0));
+        assert_eq!(1, position.to_move());
+    }
+
+    #[test]
+    fn random_games_match_game_move_generation() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..20 {
+            let mut game = Game::new();
+            game.set_draw_move_limit(None);
+            let mut position = Position::new();
+            for _ in 0..150 {
+                let to_move = (game.current_player().id - 1) as usize;
+                assert_eq!(Position::from_board(game.board(), to_move), position);
+                let moves = game_moves(&game);
+                assert_eq!(moves, position_moves(&position));
+                if moves.is_empty() || game.is_game_over() {
+                    break;
+                }
+                let path = moves.choose(&mut rng).unwrap().clone();
+                let positions: Vec<BoardPosition> = path
+                    .iter()
+                    .map(|&square| square_to_position(square as usize))
+                    .collect();
+                let is_jump = !game.get_available_jump_moves().is_empty();
+                if is_jump {
+                    game.apply_jump_move(positions).unwrap();
+                } else {
+                    game.apply_simple_move(super::super::SimpleMove::new(
+                        positions[0].row,
+                        positions[0].column,
+                        positions[1].row,
+                        positions[1].column,
+                    ))
+                    .unwrap();
+                }
+                let the_move = position
+                    .legal_moves()
+                    .into_iter()
+                    .find(|the_move| the_move.path == path)
+                    .unwrap();
+                position.apply(&the_move);
+            }
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use super::piece::ManPiece;
+use super::piece::{KingPiece, ManPiece, PieceType};
 use super::player::Player;
 use super::tile::{EmptyTile, OccupiedTile, Tile};
 
@@ -24,7 +24,6 @@ pub struct Board {
 }
 
 const CHECKERBOARD_SIZE: usize = 8;
-const CHECKERS_NUMBER_TILES: usize = CHECKERBOARD_SIZE * CHECKERBOARD_SIZE;
 
 impl Board {
     // #[cfg(test)]
@@ -48,26 +47,42 @@ impl Board {
     }
 
     pub fn new_checkerboard(player1: &Player, player2: &Player) -> Board {
+        Board::new_checkerboard_with_size(player1, player2, CHECKERBOARD_SIZE, 3)
+    }
+
+    /// Creates a `size`x`size` board with `rows_of_men` rows of men for
+    /// each player, player 1 starting on the rows with the lowest index.
+    pub fn new_checkerboard_with_size(
+        player1: &Player,
+        player2: &Player,
+        size: usize,
+        rows_of_men: usize,
+    ) -> Board {
         if player1.id == player2.id {
             panic!("Player 1 and Player 2 have the same ID: {}", player1.id)
         }
 
         let mut board = Board {
-            number_rows: CHECKERBOARD_SIZE,
-            number_columns: CHECKERBOARD_SIZE,
-            tiles: Vec::with_capacity(CHECKERS_NUMBER_TILES),
+            number_rows: size,
+            number_columns: size,
+            tiles: Vec::with_capacity(size * size),
         };
 
-        Board::fill_even_row(&mut board, player1);
-        Board::fill_odd_row(&mut board, player1);
-        Board::fill_even_row(&mut board, player1);
-
-        Board::fill_empty_row(&mut board);
-        Board::fill_empty_row(&mut board);
-
-        Board::fill_odd_row(&mut board, player2);
-        Board::fill_even_row(&mut board, player2);
-        Board::fill_odd_row(&mut board, player2);
+        for row in 0..size {
+            let player = if row < rows_of_men {
+                player1
+            } else if row >= size - rows_of_men {
+                player2
+            } else {
+                Board::fill_empty_row(&mut board);
+                continue;
+            };
+            if row % 2 == 0 {
+                Board::fill_even_row(&mut board, player);
+            } else {
+                Board::fill_odd_row(&mut board, player);
+            }
+        }
 
         board
     }
@@ -104,6 +119,21 @@ impl Board {
         self.tiles.swap(idx1, idx2);
     }
 
+    fn clone_tile(tile: &dyn Tile) -> Box<dyn Tile> {
+        match tile.get_piece() {
+            None => Box::new(EmptyTile),
+            Some(piece) => {
+                let player_id = piece.get_player_id();
+                match piece.get_type() {
+                    PieceType::Man => Box::new(OccupiedTile::new(Box::new(ManPiece { player_id }))),
+                    PieceType::King => {
+                        Box::new(OccupiedTile::new(Box::new(KingPiece { player_id })))
+                    }
+                }
+            }
+        }
+    }
+
     fn fill_even_row(board: &mut Board, player: &Player) {
         for t in 0..board.number_columns {
             let tile: Box<dyn Tile> = if t % 2 == 0 {
@@ -134,3 +164,17 @@ impl Board {
         }
     }
 }
+
+impl Clone for Board {
+    fn clone(&self) -> Board {
+        Board {
+            number_rows: self.number_rows,
+            number_columns: self.number_columns,
+            tiles: self
+                .tiles
+                .iter()
+                .map(|tile| Board::clone_tile(&**tile))
+                .collect(),
+        }
+    }
+}
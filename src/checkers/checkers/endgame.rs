@@ -0,0 +1,321 @@
+use super::bitboard::{Position, CROWN_ROWS, NUMBER_SQUARES};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+// Endgame databases
+// ---------------------------------------------------------
+// Every English draughts position with up to `max_pieces` pieces is solved
+// by retrograde analysis: positions without moves are lost, then round
+// after round a position is won when a move leads to a position lost in the
+// previous round, and lost when every move leads to a won position. What
+// is left once a round decides nothing is drawn. Draws by repetition or by
+// the move limit are not taken into account.
+
+const MAGIC: &[u8; 4] = b"ZCDB";
+const VERSION: u8 = 1;
+
+/// Game-theoretic value of a position for the player to move
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Value {
+    /// Wins in this many plies with best play from both sides
+    Win(u16),
+    /// Loses in this many plies with best play from both sides
+    Loss(u16),
+    Draw,
+}
+
+pub struct Database {
+    max_pieces: u32,
+    // drawn positions are left out
+    values: HashMap<Position, Value>,
+}
+
+// every position with `count` pieces on squares from `first` on, added to
+// the pieces already in `men` and `kings`
+fn enumerate(
+    first: usize,
+    count: u32,
+    men: [u32; 2],
+    kings: [u32; 2],
+    positions: &mut Vec<Position>,
+) {
+    if count == 0 {
+        positions.push(Position::from_masks(men, kings, 0));
+        positions.push(Position::from_masks(men, kings, 1));
+        return;
+    }
+    for square in first..NUMBER_SQUARES {
+        let mask = 1 << square;
+        for player in 0..2 {
+            // men are crowned as soon as they reach the last row
+            if CROWN_ROWS[player] & mask == 0 {
+                let mut men = men;
+                men[player] |= mask;
+                enumerate(square + 1, count - 1, men, kings, positions);
+            }
+            let mut kings = kings;
+            kings[player] |= mask;
+            enumerate(square + 1, count - 1, men, kings, positions);
+        }
+    }
+}
+
+impl Database {
+    /// Solves every position with one to `max_pieces` pieces. Three pieces
+    /// take a few seconds, every further piece multiplies the time and
+    /// memory needed by about 25.
+    pub fn build(max_pieces: u32) -> Database {
+        let mut positions = Vec::new();
+        for count in 1..=max_pieces {
+            enumerate(0, count, [0, 0], [0, 0], &mut positions);
+        }
+        let index: HashMap<Position, usize> = positions
+            .iter()
+            .enumerate()
+            .map(|(i, position)| (*position, i))
+            .collect();
+        let successors: Vec<Vec<usize>> = positions
+            .iter()
+            .map(|position| {
+                position
+                    .legal_moves()
+                    .iter()
+                    .map(|the_move| index[&position.after(the_move)])
+                    .collect()
+            })
+            .collect();
+
+        let mut values: Vec<Option<Value>> = successors
+            .iter()
+            .map(|moves| {
+                if moves.is_empty() {
+                    Some(Value::Loss(0))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        let mut plies = 0;
+        loop {
+            plies += 1;
+            let mut decided = Vec::new();
+            for (i, moves) in successors.iter().enumerate() {
+                if values[i].is_some() {
+                    continue;
+                }
+                if moves
+                    .iter()
+                    .any(|&j| values[j] == Some(Value::Loss(plies - 1)))
+                {
+                    decided.push((i, Value::Win(plies)));
+                } else if moves
+                    .iter()
+                    .all(|&j| matches!(values[j], Some(Value::Win(_))))
+                {
+                    decided.push((i, Value::Loss(plies)));
+                }
+            }
+            if decided.is_empty() {
+                break;
+            }
+            for (i, value) in decided {
+                values[i] = Some(value);
+            }
+        }
+
+        let values = positions
+            .into_iter()
+            .zip(values)
+            .filter_map(|(position, value)| value.map(|value| (position, value)))
+            .collect();
+        Database { max_pieces, values }
+    }
+
+    pub fn max_pieces(&self) -> u32 {
+        self.max_pieces
+    }
+
+    /// Number of positions that are won or lost
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// The value of `position`, `None` when it has too many pieces to be
+    /// in the database
+    pub fn probe(&self, position: &Position) -> Option<Value> {
+        if position.occupied().count_ones() > self.max_pieces {
+            return None;
+        }
+        Some(self.values.get(position).copied().unwrap_or(Value::Draw))
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_to(&mut writer)?;
+        writer.flush()
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Database> {
+        Database::read_from(&mut BufReader::new(File::open(path)?))
+    }
+
+    // header, then every won or lost position as its four masks, the
+    // player to move, 1 for a win or 0 for a loss, and the plies
+    fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION, self.max_pieces as u8])?;
+        writer.write_all(&(self.values.len() as u32).to_le_bytes())?;
+        for (position, value) in self.values.iter() {
+            for mask in [
+                position.men(0),
+                position.men(1),
+                position.kings(0),
+                position.kings(1),
+            ]
+            .iter()
+            {
+                writer.write_all(&mask.to_le_bytes())?;
+            }
+            let (won, plies) = match *value {
+                Value::Win(plies) => (1, plies),
+                Value::Loss(plies) => (0, plies),
+                Value::Draw => unreachable!(),
+            };
+            writer.write_all(&[position.to_move() as u8, won])?;
+            writer.write_all(&plies.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn read_from<R: Read>(reader: &mut R) -> io::Result<Database> {
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, "not an endgame database");
+        let mut header = [0; 10];
+        reader.read_exact(&mut header)?;
+        if &header[0..4] != MAGIC || header[4] != VERSION {
+            return Err(invalid());
+        }
+        let max_pieces = header[5] as u32;
+        let count = u32::from_le_bytes([header[6], header[7], header[8], header[9]]);
+
+        let mut values = HashMap::with_capacity(count as usize);
+        let mut entry = [0; 20];
+        for _ in 0..count {
+            reader.read_exact(&mut entry)?;
+            let mask =
+                |i: usize| u32::from_le_bytes([entry[i], entry[i + 1], entry[i + 2], entry[i + 3]]);
+            let (men, kings) = ([mask(0), mask(4)], [mask(8), mask(12)]);
+            if (men[0] | kings[0]) & (men[1] | kings[1]) != 0
+                || men[0] & kings[0] != 0
+                || men[1] & kings[1] != 0
+                || entry[16] > 1
+            {
+                return Err(invalid());
+            }
+            let plies = u16::from_le_bytes([entry[18], entry[19]]);
+            let value = match entry[17] {
+                0 => Value::Loss(plies),
+                1 => Value::Win(plies),
+                _ => return Err(invalid()),
+            };
+            values.insert(Position::from_masks(men, kings, entry[16] as usize), value);
+        }
+        Ok(Database { max_pieces, values })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use lazy_static::lazy_static;
+
+    lazy_static! {
+        static ref TWO_PIECES: Database = Database::build(2);
+    }
+
+    fn bit(square: u8) -> u32 {
+        1 << square
+    }
+
+    #[test]
+    fn player_without_pieces_has_lost() {
+        let position = Position::from_masks([0, 0], [bit(9), 0], 1);
+        assert_eq!(Some(Value::Loss(0)), TWO_PIECES.probe(&position));
+        assert_eq!(
+            Some(Value::Win(1)),
+            TWO_PIECES.probe(&position.with_to_move(0))
+        );
+    }
+
+    #[test]
+    fn capture_of_the_last_piece_wins() {
+        // the king on 9 jumps the king on 13
+        let position = Position::from_masks([0, 0], [bit(9), bit(13)], 0);
+        assert_eq!(Some(Value::Win(1)), TWO_PIECES.probe(&position));
+    }
+
+    #[test]
+    fn blocked_man_has_lost() {
+        // the man on 7 can only move to 3, which is taken
+        let position = Position::from_masks([bit(3), bit(7)], [0, 0], 1);
+        assert_eq!(Some(Value::Loss(0)), TWO_PIECES.probe(&position));
+    }
+
+    #[test]
+    fn king_in_a_double_corner_draws() {
+        let position = Position::from_masks([0, 0], [bit(3), bit(28)], 0);
+        assert_eq!(Some(Value::Draw), TWO_PIECES.probe(&position));
+    }
+
+    #[test]
+    fn king_in_a_single_corner_is_caught() {
+        // 18-22 leaves the king on 31 a single move, to 27 where it is taken
+        let position = Position::from_masks([0, 0], [bit(18), bit(31)], 0);
+        assert_eq!(Some(Value::Win(3)), TWO_PIECES.probe(&position));
+    }
+
+    #[test]
+    fn too_many_pieces_are_not_probed() {
+        assert_eq!(None, TWO_PIECES.probe(&Position::new()));
+    }
+
+    #[test]
+    fn values_agree_with_the_moves() {
+        for (position, value) in TWO_PIECES.values.iter() {
+            let values: Vec<Value> = position
+                .legal_moves()
+                .iter()
+                .map(|the_move| TWO_PIECES.probe(&position.after(the_move)).unwrap())
+                .collect();
+            match *value {
+                Value::Win(plies) => assert!(values.contains(&Value::Loss(plies - 1))),
+                Value::Loss(plies) => {
+                    assert!(values.iter().all(|value| match value {
+                        Value::Win(reply) => *reply < plies,
+                        _ => false,
+                    }));
+                    assert!(plies == 0 || values.contains(&Value::Win(plies - 1)));
+                }
+                Value::Draw => unreachable!(),
+            }
+        }
+    }
+
+    #[test]
+    fn file_round_trip() {
+        let path = std::env::temp_dir().join("zarena_endgame_test.db");
+        TWO_PIECES.save(&path).unwrap();
+        let loaded = Database::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(2, loaded.max_pieces());
+        assert_eq!(TWO_PIECES.values, loaded.values);
+
+        let mut garbage: &[u8] = b"ZCDB\x02\x02";
+        assert!(Database::read_from(&mut garbage).is_err());
+    }
+}
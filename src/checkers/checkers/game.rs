@@ -3,10 +3,13 @@
 #[allow(unused_imports)]
 use super::super::checkers;
 
+use super::super::util;
 use super::{
-    ai, Board, BoardPosition, Direction, JumpMove, KingPiece, OccupiedTile, PieceType, Player,
-    SimpleMove,
+    ai, Board, BoardPosition, Direction, JumpMove, KingPiece, ManPiece, OccupiedTile, Piece,
+    PieceType, Player, Rules, SimpleMove,
 };
+use std::cmp::Reverse;
+use std::collections::HashMap;
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum GameState {
@@ -15,6 +18,10 @@ pub enum GameState {
 
     /// The game has finished. The `u32` is the ID of the winning player.
     GameOver { winner_id: u32 },
+
+    /// The game has finished without a winner, either because the same
+    /// position was repeated three times or because of the move limit.
+    Draw,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -23,19 +30,50 @@ pub enum MoveError {
     ShouldHaveJumped,
 }
 
+/// A move played in a game, with what is needed to take it back
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MoveRecord {
+    /// Positions visited by the moving piece
+    pub path: Vec<BoardPosition>,
+    /// Positions and types of the captured pieces
+    pub captured: Vec<(BoardPosition, PieceType)>,
+    /// The moving man was crowned
+    pub crowned: bool,
+
+    // draw bookkeeping from before the move, the position counts are only
+    // kept when the move cleared them
+    quiet_plies: usize,
+    draw: bool,
+    position_counts: Option<HashMap<Vec<u8>, usize>>,
+}
+
+#[derive(Clone)]
 struct PlayerInfo {
     player: Player,
     direction: Direction,
 }
 
+#[derive(Clone)]
 pub struct Game {
     players: [PlayerInfo; 2],
     board: Board,
+    rules: Rules,
 
     current_player_index: usize,
 
     available_simple_moves: Vec<SimpleMove>,
     available_jump_moves: Vec<JumpMove>,
+
+    // occurrences of every position since the last irreversible move
+    position_counts: HashMap<Vec<u8>, usize>,
+    // plies played since the last capture or man move
+    quiet_plies: usize,
+    draw_move_limit: Option<usize>,
+    draw: bool,
+
+    history: Vec<MoveRecord>,
+    // moves taken back, the last one undone at the end
+    undone: Vec<MoveRecord>,
 }
 
 impl Game {
@@ -47,6 +85,27 @@ impl Game {
         Game::with_board_and_players(board, player1, player2)
     }
 
+    /// Creates a new game of the draughts variant described by `rules`
+    pub fn with_rules(rules: Rules) -> Game {
+        let (player1, player2) = Game::create_two_players();
+
+        let board = Board::new_checkerboard_with_size(
+            &player1,
+            &player2,
+            rules.board_size,
+            rules.rows_of_men,
+        );
+
+        let mut game = Game::with_board_and_players(board, player1, player2);
+        game.rules = rules;
+        game.find_available_moves();
+        game
+    }
+
+    pub fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
     /// Creates a new Checkers game with an 8x8 board and the specified piece
     /// layout `player1_positions` contains the pieces for the first player,
     /// and `player2_positions` contains the pieces for the second player. All
@@ -79,6 +138,23 @@ impl Game {
 
     pub fn set_board(&mut self, board: Board) {
         self.board = board;
+        self.position_counts.clear();
+        self.quiet_plies = 0;
+        self.draw = false;
+        self.history.clear();
+        self.undone.clear();
+        self.record_position();
+    }
+
+    /// Sets the number of moves per player without captures or man moves
+    /// after which the game is drawn, 40 in tournament English draughts.
+    /// `None`, the default, disables the rule.
+    pub fn set_draw_move_limit(&mut self, limit: Option<usize>) {
+        self.draw_move_limit = limit;
+    }
+
+    pub fn draw_move_limit(&self) -> Option<usize> {
+        self.draw_move_limit
     }
 
     pub fn get_board(&self) -> &Board {
@@ -123,11 +199,19 @@ impl Game {
         let mut game = Game {
             players: [player1_info, player2_info],
             board: board,
+            rules: Rules::english(),
             current_player_index: 0,
             available_simple_moves: Vec::new(),
             available_jump_moves: Vec::new(),
+            position_counts: HashMap::new(),
+            quiet_plies: 0,
+            draw_move_limit: None,
+            draw: false,
+            history: Vec::new(),
+            undone: Vec::new(),
         };
 
+        game.record_position();
         game.find_available_moves();
 
         game
@@ -153,6 +237,9 @@ impl Game {
                                 PieceType::Man => {
                                     ai::find_simple_moves_for_man(&self.board, curr_direction, r, c)
                                 }
+                                PieceType::King if self.rules.flying_kings => {
+                                    ai::find_simple_moves_for_flying_king(&self.board, r, c)
+                                }
                                 PieceType::King => {
                                     ai::find_simple_moves_for_king(&self.board, r, c)
                                 }
@@ -181,6 +268,17 @@ impl Game {
                     Some(piece) => {
                         if piece.get_player_id() == curr_player_id {
                             let jump_move = match piece.get_type() {
+                                piece_type if self.rules != Rules::english() => {
+                                    ai::find_jump_moves_with_rules(
+                                        &self.board,
+                                        curr_player,
+                                        curr_direction,
+                                        piece_type,
+                                        &self.rules,
+                                        r,
+                                        c,
+                                    )
+                                }
                                 PieceType::Man => ai::find_jump_moves_for_man(
                                     &self.board,
                                     curr_player,
@@ -202,9 +300,102 @@ impl Game {
             }
         }
 
+        if self.rules.max_capture {
+            moves = self.keep_best_captures(moves);
+        }
+
         moves
     }
 
+    // Applies the maximum capture rule, and the Italian tie-breaks when
+    // `capture_quality` is set, keeping only the best capture sequences.
+    fn keep_best_captures(&self, moves: Vec<JumpMove>) -> Vec<JumpMove> {
+        let sequences: Vec<Vec<BoardPosition>> = moves
+            .iter()
+            .flat_map(|jump_move| jump_move.sequences())
+            .collect();
+        let scores: Vec<_> = sequences
+            .iter()
+            .map(|sequence| {
+                let captured = self.captured_positions(sequence);
+                if !self.rules.capture_quality {
+                    return (captured.len(), false, 0, Reverse(0));
+                }
+                let is_king = |position: &BoardPosition| {
+                    matches!(
+                        self.board
+                            .get_tile(position.row, position.column)
+                            .get_piece()
+                            .map(|piece| piece.get_type()),
+                        Some(PieceType::King)
+                    )
+                };
+                let kings = captured.iter().filter(|position| is_king(position)).count();
+                let first_king = captured
+                    .iter()
+                    .position(|position| is_king(position))
+                    .unwrap_or(captured.len());
+                (
+                    captured.len(),
+                    is_king(&sequence[0]),
+                    kings,
+                    Reverse(first_king),
+                )
+            })
+            .collect();
+        let best = match scores.iter().max() {
+            Some(best) => *best,
+            None => return moves,
+        };
+        let best_sequences: Vec<Vec<BoardPosition>> = sequences
+            .into_iter()
+            .zip(scores)
+            .filter(|(_, score)| *score == best)
+            .map(|(sequence, _)| sequence)
+            .collect();
+        JumpMove::from_sequences(&best_sequences)
+    }
+
+    /// The positions of the pieces captured along a jump sequence, in the
+    /// order they are jumped.
+    pub fn captured_positions(&self, the_move: &[BoardPosition]) -> Vec<BoardPosition> {
+        let mover_id = match the_move.first().and_then(|position| {
+            self.board
+                .get_tile(position.row, position.column)
+                .get_piece()
+        }) {
+            Some(piece) => piece.get_player_id(),
+            None => return Vec::new(),
+        };
+        let mut captured = Vec::new();
+        let iter = the_move[0..].iter().zip(the_move[1..].iter());
+        for (jump_from_pos, jump_to_pos) in iter {
+            let distance = util::absolute_diff(jump_from_pos.row, jump_to_pos.row);
+            for i in 1..distance {
+                let row = if jump_to_pos.row > jump_from_pos.row {
+                    jump_from_pos.row + i
+                } else {
+                    jump_from_pos.row - i
+                };
+                let col = if jump_to_pos.column > jump_from_pos.column {
+                    jump_from_pos.column + i
+                } else {
+                    jump_from_pos.column - i
+                };
+                let enemy = self
+                    .board
+                    .get_tile(row, col)
+                    .get_piece()
+                    .map(|piece| piece.get_player_id() != mover_id)
+                    .unwrap_or(false);
+                if enemy {
+                    captured.push(BoardPosition::new(row, col));
+                }
+            }
+        }
+        captured
+    }
+
     pub fn find_available_moves(&mut self) {
         self.available_simple_moves = self.find_available_simple_moves();
         self.available_jump_moves = self.find_available_jump_moves();
@@ -218,13 +409,18 @@ impl Game {
         &self.current_player_info().player
     }
 
-    fn check_for_coronation(&mut self, row: usize, col: usize) {
+    fn is_crown_row(&self, row: usize) -> bool {
+        match self.current_player_info().direction {
+            Direction::IncreasingRank => row + 1 == self.board.number_rows(),
+            Direction::DecreasingRank => row == 0,
+        }
+    }
+
+    // returns whether the piece was crowned
+    fn check_for_coronation(&mut self, row: usize, col: usize, crossed_crown_row: bool) -> bool {
         let coronate = match self.board.get_tile(row, col).get_piece() {
             Some(piece) => match piece.get_type() {
-                PieceType::Man => match self.current_player_info().direction {
-                    Direction::IncreasingRank => row + 1 == self.board.number_rows(),
-                    Direction::DecreasingRank => row == 0,
-                },
+                PieceType::Man => crossed_crown_row || self.is_crown_row(row),
                 PieceType::King => false,
             },
             None => unreachable!(),
@@ -235,6 +431,7 @@ impl Game {
             let tile = OccupiedTile::new(Box::new(king));
             self.board.set_tile(row, col, Box::new(tile))
         }
+        coronate
     }
 
     fn select_next_player(&mut self) {
@@ -243,22 +440,93 @@ impl Game {
     }
 
     pub fn is_game_over(&self) -> bool {
+        self.is_draw() || !self.has_moves()
+    }
+
+    pub fn is_draw(&self) -> bool {
+        self.draw
+    }
+
+    fn has_moves(&self) -> bool {
         // This works if it is called after the available moves for the
         // next player are computed. If this player has no moves, it means
         // they have no pieces left, or all of their pieces are stuck.
         // Either way, they lose.
-        self.available_simple_moves.is_empty() && self.available_jump_moves.is_empty()
+        !(self.available_simple_moves.is_empty() && self.available_jump_moves.is_empty())
     }
 
-    fn finish_move(&mut self, final_row: usize, final_col: usize) -> GameState {
-        self.check_for_coronation(final_row, final_col);
+    // the pieces on the board and the player to move
+    fn position_key(&self) -> Vec<u8> {
+        let mut key =
+            Vec::with_capacity(self.board.number_rows() * self.board.number_columns() + 1);
+        for r in 0..self.board.number_rows() {
+            for c in 0..self.board.number_columns() {
+                key.push(match self.board.get_tile(r, c).get_piece() {
+                    None => 0,
+                    Some(piece) => match piece.get_type() {
+                        PieceType::Man => 2 * piece.get_player_id() as u8 - 1,
+                        PieceType::King => 2 * piece.get_player_id() as u8,
+                    },
+                });
+            }
+        }
+        key.push(self.current_player_index as u8);
+        key
+    }
+
+    // returns how many times the current position has been seen
+    fn record_position(&mut self) -> usize {
+        let count = self.position_counts.entry(self.position_key()).or_insert(0);
+        *count += 1;
+        *count
+    }
+
+    // the record of a move about to be played along `path`
+    fn start_record(&self, path: Vec<BoardPosition>) -> MoveRecord {
+        MoveRecord {
+            path,
+            captured: Vec::new(),
+            crowned: false,
+            quiet_plies: self.quiet_plies,
+            draw: self.draw,
+            position_counts: None,
+        }
+    }
+
+    fn finish_move(
+        &mut self,
+        mut record: MoveRecord,
+        irreversible: bool,
+        crossed_crown_row: bool,
+    ) -> GameState {
+        let final_position = *record.path.last().unwrap();
+        record.crowned =
+            self.check_for_coronation(final_position.row, final_position.column, crossed_crown_row);
 
         self.select_next_player();
         self.find_available_moves();
 
-        if self.is_game_over() {
+        if irreversible {
+            // earlier positions can not appear again
+            record.position_counts = Some(std::mem::take(&mut self.position_counts));
+            self.quiet_plies = 0;
+        } else {
+            self.quiet_plies += 1;
+        }
+        self.history.push(record);
+        self.undone.clear();
+        let repetitions = self.record_position();
+        let move_limit_reached = self
+            .draw_move_limit
+            .map(|limit| self.quiet_plies >= 2 * limit)
+            .unwrap_or(false);
+
+        if !self.has_moves() {
             let winner_id = self.players[1 - self.current_player_index].player.id;
             GameState::GameOver { winner_id }
+        } else if repetitions >= 3 || move_limit_reached {
+            self.draw = true;
+            GameState::Draw
         } else {
             GameState::InProgress
         }
@@ -267,6 +535,21 @@ impl Game {
     pub fn apply_simple_move(&mut self, the_move: SimpleMove) -> Result<GameState, MoveError> {
         if self.available_jump_moves.is_empty() {
             if self.available_simple_moves.contains(&the_move) {
+                let man_move = match self
+                    .board
+                    .get_tile(the_move.from_row(), the_move.from_column())
+                    .get_piece()
+                {
+                    Some(piece) => match piece.get_type() {
+                        PieceType::Man => true,
+                        PieceType::King => false,
+                    },
+                    None => unreachable!(),
+                };
+                let record = self.start_record(vec![
+                    BoardPosition::new(the_move.from_row(), the_move.from_column()),
+                    BoardPosition::new(the_move.to_row(), the_move.to_column()),
+                ]);
                 self.board.swap_tiles(
                     the_move.from_row(),
                     the_move.from_column(),
@@ -274,7 +557,7 @@ impl Game {
                     the_move.to_column(),
                 );
 
-                let game_state = self.finish_move(the_move.to_row(), the_move.to_column());
+                let game_state = self.finish_move(record, man_move, false);
                 Ok(game_state)
             } else {
                 Err(MoveError::InvalidMove)
@@ -288,13 +571,19 @@ impl Game {
         &mut self,
         the_move: Vec<BoardPosition>,
     ) -> Result<GameState, MoveError> {
+        // a capture has to be carried on until no further jump is available
         let jump_valid = self
             .available_jump_moves
             .iter()
-            .any(|jump_tree| jump_tree.contains_jump_sequence(&the_move));
+            .any(|jump_tree| jump_tree.contains_complete_jump_sequence(&the_move));
         if jump_valid {
-            let start_position = the_move.first().unwrap();
-            let final_position = the_move.last().unwrap();
+            let start_position = *the_move.first().unwrap();
+            let final_position = *the_move.last().unwrap();
+            let captured = self.captured_positions(&the_move);
+            let crossed_crown_row = self.rules.crown_during_capture
+                && the_move[1..]
+                    .iter()
+                    .any(|position| self.is_crown_row(position.row));
 
             // move the jumping piece
             self.board.swap_tiles(
@@ -305,19 +594,102 @@ impl Game {
             );
 
             // remove all jumped pieces
-            let iter = the_move[0..].iter().zip(the_move[1..].iter());
-            for (jump_from_pos, jump_to_pos) in iter {
-                let jumped_row = (jump_from_pos.row + jump_to_pos.row) / 2;
-                let jumped_col = (jump_from_pos.column + jump_to_pos.column) / 2;
-                self.board.clear_tile(jumped_row, jumped_col);
+            let mut record = self.start_record(the_move);
+            for position in captured {
+                let piece_type = self
+                    .board
+                    .get_tile(position.row, position.column)
+                    .get_piece()
+                    .unwrap()
+                    .get_type();
+                record.captured.push((position, piece_type));
+                self.board.clear_tile(position.row, position.column);
             }
 
-            let game_state = self.finish_move(final_position.row, final_position.column);
+            let game_state = self.finish_move(record, true, crossed_crown_row);
             Ok(game_state)
         } else {
             Err(MoveError::InvalidMove)
         }
     }
+
+    /// The moves played since the start of the game, or since the board
+    /// was last set, the last one at the end
+    pub fn history(&self) -> &[MoveRecord] {
+        &self.history
+    }
+
+    /// Takes back the last move. Returns `false` when there is no move to
+    /// take back.
+    pub fn undo_move(&mut self) -> bool {
+        let mut record = match self.history.pop() {
+            Some(record) => record,
+            None => return false,
+        };
+
+        match record.position_counts.take() {
+            Some(position_counts) => self.position_counts = position_counts,
+            None => {
+                let key = self.position_key();
+                if let Some(count) = self.position_counts.get_mut(&key) {
+                    *count -= 1;
+                    if *count == 0 {
+                        self.position_counts.remove(&key);
+                    }
+                }
+            }
+        }
+        self.quiet_plies = record.quiet_plies;
+        self.draw = record.draw;
+        self.select_next_player();
+
+        let start = record.path[0];
+        let end = *record.path.last().unwrap();
+        if start != end {
+            self.board
+                .swap_tiles(end.row, end.column, start.row, start.column);
+        }
+        if record.crowned {
+            let man = ManPiece::new(&self.current_player_info().player);
+            let tile = OccupiedTile::new(Box::new(man));
+            self.board.set_tile(start.row, start.column, Box::new(tile));
+        }
+        let opponent = self.players[1 - self.current_player_index].player.clone();
+        for (position, piece_type) in record.captured.iter() {
+            let piece: Box<dyn Piece> = match piece_type {
+                PieceType::Man => Box::new(ManPiece::new(&opponent)),
+                PieceType::King => Box::new(KingPiece::new(&opponent)),
+            };
+            let tile = OccupiedTile::new(piece);
+            self.board
+                .set_tile(position.row, position.column, Box::new(tile));
+        }
+        self.find_available_moves();
+
+        self.undone.push(record);
+        true
+    }
+
+    /// Plays again the last move taken back by `undo_move`. Returns `None`
+    /// when there is no move to replay.
+    pub fn redo_move(&mut self) -> Option<GameState> {
+        let record = self.undone.pop()?;
+        // playing a move forgets the moves taken back, keep them
+        let undone = std::mem::take(&mut self.undone);
+        let result = if record.captured.is_empty() {
+            let (start, end) = (record.path[0], record.path[1]);
+            self.apply_simple_move(SimpleMove::new(
+                start.row,
+                start.column,
+                end.row,
+                end.column,
+            ))
+        } else {
+            self.apply_jump_move(record.path)
+        };
+        self.undone = undone;
+        result.ok()
+    }
 }
 
 #[cfg(test)]
@@ -409,6 +781,23 @@ mod test {
         assert!(jumping_piece.is_some());
     }
 
+    #[test]
+    fn incomplete_multi_jump_move() {
+        let mut game = Game::with_piece_positions(
+            vec![BoardPosition::new(3, 3)],
+            vec![
+                BoardPosition::new(4, 4),
+                BoardPosition::new(6, 4),
+                BoardPosition::new(2, 2),
+            ],
+        );
+
+        let result = game.apply_jump_move(vec![BoardPosition::new(3, 3), BoardPosition::new(5, 5)]);
+        let exp_result: Result<GameState, MoveError> = Err(MoveError::InvalidMove);
+        assert_eq!(exp_result, result);
+        assert!(game.board().get_tile(4, 4).get_piece().is_some());
+    }
+
     #[test]
     fn bad_jump_move() {
         let mut game = Game::new();
@@ -472,6 +861,64 @@ mod test {
         }
     }
 
+    fn shuffle_kings(game: &mut Game, times: usize) -> Result<GameState, MoveError> {
+        let mut result = Ok(GameState::InProgress);
+        for _ in 0..times {
+            game.apply_simple_move(SimpleMove::new(0, 0, 1, 1))?;
+            game.apply_simple_move(SimpleMove::new(7, 7, 6, 6))?;
+            game.apply_simple_move(SimpleMove::new(1, 1, 0, 0))?;
+            result = game.apply_simple_move(SimpleMove::new(6, 6, 7, 7));
+        }
+        result
+    }
+
+    fn two_kings() -> Game {
+        let mut board = Board::new(8, 8);
+        let king_1 = KingPiece::new(&Player { id: 1 });
+        let king_2 = KingPiece::new(&Player { id: 2 });
+        board.set_tile(0, 0, Box::new(OccupiedTile::new(Box::new(king_1))));
+        board.set_tile(7, 7, Box::new(OccupiedTile::new(Box::new(king_2))));
+        let mut game = Game::new();
+        game.set_current_player(0);
+        game.set_board(board);
+        game.find_available_moves();
+        game
+    }
+
+    #[test]
+    fn draw_by_threefold_repetition() {
+        let mut game = two_kings();
+
+        assert_eq!(Ok(GameState::InProgress), shuffle_kings(&mut game, 1));
+        assert!(!game.is_game_over());
+
+        assert_eq!(Ok(GameState::Draw), shuffle_kings(&mut game, 1));
+        assert!(game.is_draw());
+        assert!(game.is_game_over());
+    }
+
+    #[test]
+    fn draw_by_move_limit() {
+        let mut game = two_kings();
+        // the rule is opt-in
+        assert_eq!(None, game.draw_move_limit());
+        game.set_draw_move_limit(Some(1));
+
+        let result = game.apply_simple_move(SimpleMove::new(0, 0, 1, 1));
+        assert_eq!(Ok(GameState::InProgress), result);
+        let result = game.apply_simple_move(SimpleMove::new(7, 7, 6, 6));
+        assert_eq!(Ok(GameState::Draw), result);
+    }
+
+    #[test]
+    fn no_repetition_draw_when_disabled_limit_and_man_moves() {
+        let mut game = Game::new();
+        game.set_draw_move_limit(None);
+        let result = game.apply_simple_move(SimpleMove::new(2, 0, 3, 1));
+        assert_eq!(Ok(GameState::InProgress), result);
+        assert!(!game.is_draw());
+    }
+
     #[test]
     fn game_over_when_no_moves_for_current_player() {
         let mut game = Game::with_piece_positions(vec![BoardPosition::new(4, 4)], vec![]);
@@ -480,4 +927,206 @@ mod test {
         let exp_result: Result<GameState, MoveError> = Ok(GameState::GameOver { winner_id: 1 });
         assert_eq!(exp_result, result);
     }
+
+    fn variant_game(rules: Rules, pieces: &[(usize, usize, u32, PieceType)]) -> Game {
+        let mut board = Board::new(rules.board_size, rules.board_size);
+        for &(row, col, player_id, piece_type) in pieces {
+            let player = Player { id: player_id };
+            let tile = match piece_type {
+                PieceType::Man => OccupiedTile::new(Box::new(checkers::ManPiece::new(&player))),
+                PieceType::King => OccupiedTile::new(Box::new(KingPiece::new(&player))),
+            };
+            board.set_tile(row, col, Box::new(tile));
+        }
+        let mut game = Game::with_rules(rules);
+        game.set_current_player(0);
+        game.set_board(board);
+        game.find_available_moves();
+        game
+    }
+
+    fn jump_sequences(game: &Game) -> Vec<Vec<BoardPosition>> {
+        game.get_available_jump_moves()
+            .iter()
+            .flat_map(|jump_move| jump_move.sequences())
+            .collect()
+    }
+
+    #[test]
+    fn international_start() {
+        let game = Game::with_rules(Rules::international());
+        assert_eq!(10, game.board().number_rows());
+        assert_eq!(9, game.get_available_simple_moves().len());
+        assert!(game.get_available_jump_moves().is_empty());
+    }
+
+    #[test]
+    fn flying_king_moves_and_captures() {
+        let game = variant_game(Rules::international(), &[(0, 0, 1, PieceType::King)]);
+        assert_eq!(9, game.get_available_simple_moves().len());
+
+        let mut game = variant_game(
+            Rules::international(),
+            &[(0, 0, 1, PieceType::King), (3, 3, 2, PieceType::Man)],
+        );
+        let sequences = jump_sequences(&game);
+        assert_eq!(6, sequences.len());
+        let result = game.apply_jump_move(vec![BoardPosition::new(0, 0), BoardPosition::new(6, 6)]);
+        assert_eq!(Ok(GameState::GameOver { winner_id: 1 }), result);
+        assert!(game.board().get_tile(3, 3).get_piece().is_none());
+        assert!(game.board().get_tile(6, 6).get_piece().is_some());
+    }
+
+    #[test]
+    fn men_capture_backwards() {
+        let pieces = [(4, 4, 1, PieceType::Man), (3, 3, 2, PieceType::Man)];
+        let game = variant_game(Rules::international(), &pieces);
+        assert_eq!(
+            vec![vec![BoardPosition::new(4, 4), BoardPosition::new(2, 2)]],
+            jump_sequences(&game)
+        );
+
+        let game = variant_game(Rules::english(), &pieces);
+        assert!(game.get_available_jump_moves().is_empty());
+    }
+
+    #[test]
+    fn maximum_capture_is_mandatory() {
+        let pieces = [
+            (2, 2, 1, PieceType::Man),
+            (2, 6, 1, PieceType::Man),
+            (3, 3, 2, PieceType::Man),
+            (3, 7, 2, PieceType::Man),
+            (5, 7, 2, PieceType::Man),
+        ];
+        let game = variant_game(Rules::international(), &pieces);
+        assert_eq!(
+            vec![vec![
+                BoardPosition::new(2, 6),
+                BoardPosition::new(4, 8),
+                BoardPosition::new(6, 6)
+            ]],
+            jump_sequences(&game)
+        );
+
+        let rules = Rules {
+            max_capture: false,
+            ..Rules::international()
+        };
+        let game = variant_game(rules, &pieces);
+        assert_eq!(2, jump_sequences(&game).len());
+    }
+
+    #[test]
+    fn russian_man_crowned_during_capture() {
+        let mut game = variant_game(
+            Rules::russian(),
+            &[
+                (5, 1, 1, PieceType::Man),
+                (6, 2, 2, PieceType::Man),
+                (5, 5, 2, PieceType::Man),
+            ],
+        );
+        let mut sequences = jump_sequences(&game);
+        sequences.sort_by_key(|sequence| sequence[2].row);
+        let crowned_capture = vec![
+            BoardPosition::new(5, 1),
+            BoardPosition::new(7, 3),
+            BoardPosition::new(3, 7),
+        ];
+        assert_eq!(2, sequences.len());
+        assert_eq!(crowned_capture, sequences[0]);
+
+        game.apply_jump_move(crowned_capture).unwrap();
+        let piece = game.board().get_tile(3, 7).get_piece().unwrap();
+        assert_eq!(PieceType::King, piece.get_type());
+    }
+
+    #[test]
+    fn italian_men_cannot_capture_kings() {
+        let game = variant_game(
+            Rules::italian(),
+            &[(2, 2, 1, PieceType::Man), (3, 3, 2, PieceType::King)],
+        );
+        assert!(game.get_available_jump_moves().is_empty());
+        assert_eq!(1, game.get_available_simple_moves().len());
+    }
+
+    fn board_key(game: &Game) -> (Vec<u8>, usize, usize) {
+        (
+            game.position_key(),
+            game.available_simple_moves.len(),
+            game.available_jump_moves.len(),
+        )
+    }
+
+    #[test]
+    fn undo_and_redo_moves() {
+        let mut game = Game::new();
+        let start = board_key(&game);
+        game.apply_simple_move(SimpleMove::new(2, 2, 3, 3)).unwrap();
+        let after_first = board_key(&game);
+        game.apply_simple_move(SimpleMove::new(5, 5, 4, 4)).unwrap();
+        let after_second = board_key(&game);
+        assert_eq!(2, game.history().len());
+
+        assert!(game.undo_move());
+        assert_eq!(after_first, board_key(&game));
+        assert!(game.undo_move());
+        assert_eq!(start, board_key(&game));
+        assert!(!game.undo_move());
+
+        assert_eq!(Some(GameState::InProgress), game.redo_move());
+        assert_eq!(Some(GameState::InProgress), game.redo_move());
+        assert_eq!(after_second, board_key(&game));
+        assert_eq!(None, game.redo_move());
+
+        // a new move forgets the moves taken back
+        game.undo_move();
+        game.apply_simple_move(SimpleMove::new(5, 1, 4, 0)).unwrap();
+        assert_eq!(None, game.redo_move());
+    }
+
+    #[test]
+    fn undo_capture_and_coronation() {
+        let mut game = variant_game(
+            Rules::english(),
+            &[
+                (5, 1, 1, PieceType::Man),
+                (6, 2, 2, PieceType::King),
+                (0, 6, 2, PieceType::Man),
+            ],
+        );
+        let before = board_key(&game);
+        let path = vec![BoardPosition::new(5, 1), BoardPosition::new(7, 3)];
+        game.apply_jump_move(path.clone()).unwrap();
+
+        let record = &game.history()[0];
+        assert_eq!(path, record.path);
+        assert_eq!(
+            vec![(BoardPosition::new(6, 2), PieceType::King)],
+            record.captured
+        );
+        assert!(record.crowned);
+
+        assert!(game.undo_move());
+        assert_eq!(before, board_key(&game));
+        let piece = game.board().get_tile(6, 2).get_piece().unwrap();
+        assert_eq!(
+            (2, PieceType::King),
+            (piece.get_player_id(), piece.get_type())
+        );
+    }
+
+    #[test]
+    fn undo_restores_draw_state() {
+        let mut game = two_kings();
+        assert_eq!(Ok(GameState::InProgress), shuffle_kings(&mut game, 1));
+        assert_eq!(Ok(GameState::Draw), shuffle_kings(&mut game, 1));
+
+        assert!(game.undo_move());
+        assert!(!game.is_draw());
+        assert_eq!(Some(GameState::Draw), game.redo_move());
+        assert!(game.is_draw());
+    }
 }
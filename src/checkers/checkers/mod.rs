@@ -1,26 +1,40 @@
 mod ai;
 pub use ai::{
-    find_jump_moves_for_king, find_jump_moves_for_man, find_simple_moves_for_king,
-    find_simple_moves_for_man, Direction, JumpMove, SimpleMove,
+    find_jump_moves_for_king, find_jump_moves_for_man, find_jump_moves_with_rules,
+    find_simple_moves_for_flying_king, find_simple_moves_for_king, find_simple_moves_for_man,
+    Direction, JumpMove, SimpleMove,
 };
 
+pub mod bitboard;
+
 mod board;
 pub use board::{Board, BoardPosition};
 
 mod display;
 pub use display::print_board;
 
+pub mod endgame;
+
 mod game;
 pub use game::{Game, GameState, MoveError};
 
 mod input;
 pub use input::{parse_move, InputError, TokenError};
 
+pub mod pdn;
+
+pub mod perft;
+
 mod piece;
 pub use piece::{KingPiece, ManPiece, Piece, PieceType};
 
 mod player;
 pub use player::Player;
 
+mod rules;
+pub use rules::Rules;
+
+pub mod search;
+
 mod tile;
 pub use tile::{EmptyTile, OccupiedTile, Tile};
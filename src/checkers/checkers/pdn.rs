@@ -0,0 +1,542 @@
+use super::bitboard::{BitMove, Position, NUMBER_SQUARES};
+use std::fmt;
+
+// Portable Draughts Notation
+// ---------------------------------------------------------
+// PDN numbers the dark squares 1-32 from the side of Black, who moves first
+// and starts on squares 1-12, and which corresponds to player 1 here. Row by
+// row the PDN numbers run in the opposite direction of the engine squares:
+//
+//     PDN      1  2  3  4      5  6  7  8    ...
+//     engine   3  2  1  0      7  6  5  4    ...
+//
+// Results are given from the point of view of Black: `1-0` is a win for
+// Black (player 1), `0-1` a win for White (player 2).
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum PdnError {
+    InvalidSquare { token: String },
+    InvalidMove { token: String },
+    IllegalMove { token: String },
+    AmbiguousMove { token: String },
+    InvalidFen { fen: String },
+    InvalidTag { tag: String },
+}
+
+impl fmt::Display for PdnError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PdnError::InvalidSquare { token } => write!(f, "invalid square '{}'", token),
+            PdnError::InvalidMove { token } => write!(f, "invalid move '{}'", token),
+            PdnError::IllegalMove { token } => write!(f, "illegal move '{}'", token),
+            PdnError::AmbiguousMove { token } => write!(f, "ambiguous move '{}'", token),
+            PdnError::InvalidFen { fen } => write!(f, "invalid FEN '{}'", fen),
+            PdnError::InvalidTag { tag } => write!(f, "invalid tag '{}'", tag),
+        }
+    }
+}
+
+pub const RESULT_PLAYER_1_WINS: &str = "1-0";
+pub const RESULT_PLAYER_2_WINS: &str = "0-1";
+pub const RESULT_DRAW: &str = "1/2-1/2";
+pub const RESULT_UNKNOWN: &str = "*";
+
+/// Converts a PDN square number (1-32) to an engine square (0-31)
+pub fn pdn_to_square(number: usize) -> Option<usize> {
+    if number == 0 || number > NUMBER_SQUARES {
+        return None;
+    }
+    let k = number - 1;
+    Some(4 * (k / 4) + 3 - k % 4)
+}
+
+/// Converts an engine square (0-31) to its PDN number (1-32)
+pub fn square_to_pdn(square: usize) -> usize {
+    4 * (square / 4) + 3 - square % 4 + 1
+}
+
+fn parse_square(token: &str) -> Result<usize, PdnError> {
+    token
+        .trim()
+        .parse::<usize>()
+        .ok()
+        .and_then(pdn_to_square)
+        .ok_or_else(|| PdnError::InvalidSquare {
+            token: token.to_string(),
+        })
+}
+
+// Square lists of a FEN tag, e.g. `K1,2,5-8`
+fn parse_square_list(list: &str, fen: &str) -> Result<(u32, u32), PdnError> {
+    let invalid = || PdnError::InvalidFen {
+        fen: fen.to_string(),
+    };
+    let (mut men, mut kings) = (0, 0);
+    for item in list
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+    {
+        let (is_king, item) = match item.strip_prefix('K') {
+            Some(rest) => (true, rest),
+            None => (false, item),
+        };
+        let (first, last) = item.split_once('-').unwrap_or((item, item));
+        let first: usize = first.parse().map_err(|_| invalid())?;
+        let last: usize = last.parse().map_err(|_| invalid())?;
+        // both ends are checked before walking the range
+        if first > last || pdn_to_square(first).is_none() || pdn_to_square(last).is_none() {
+            return Err(invalid());
+        }
+        for number in first..=last {
+            let square = pdn_to_square(number).unwrap();
+            if is_king {
+                kings |= 1 << square;
+            } else {
+                men |= 1 << square;
+            }
+        }
+    }
+    Ok((men, kings))
+}
+
+/// Parses a FEN tag value such as `B:W21,22,K30:B1,2,3`. `B` (Black) is
+/// player 1 and `W` (White) player 2.
+pub fn parse_fen(fen: &str) -> Result<Position, PdnError> {
+    let invalid = || PdnError::InvalidFen {
+        fen: fen.to_string(),
+    };
+    let fen = fen.trim().trim_end_matches('.');
+    let mut sections = fen.split(':');
+    let to_move = match sections.next().map(str::trim) {
+        Some("B") => 0,
+        Some("W") => 1,
+        _ => return Err(invalid()),
+    };
+    let mut men = [0, 0];
+    let mut kings = [0, 0];
+    for section in sections {
+        let section = section.trim();
+        let player = match section.chars().next() {
+            Some('B') => 0,
+            Some('W') => 1,
+            _ => return Err(invalid()),
+        };
+        let (player_men, player_kings) = parse_square_list(&section[1..], fen)?;
+        men[player] |= player_men;
+        kings[player] |= player_kings;
+    }
+    let overlapping = (men[0] | kings[0]) & (men[1] | kings[1]) != 0
+        || men[0] & kings[0] != 0
+        || men[1] & kings[1] != 0;
+    if overlapping {
+        return Err(invalid());
+    }
+    Ok(Position::from_masks(men, kings, to_move))
+}
+
+/// Writes the FEN tag value of a position
+pub fn to_fen(position: &Position) -> String {
+    let square_list = |player: usize| {
+        let mut items: Vec<(usize, String)> = (0..NUMBER_SQUARES)
+            .filter_map(|square| {
+                let number = square_to_pdn(square);
+                if position.men(player) & (1 << square) != 0 {
+                    Some((number, number.to_string()))
+                } else if position.kings(player) & (1 << square) != 0 {
+                    Some((number, format!("K{}", number)))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        items.sort();
+        items
+            .into_iter()
+            .map(|(_, item)| item)
+            .collect::<Vec<_>>()
+            .join(",")
+    };
+    let to_move = if position.to_move() == 0 { "B" } else { "W" };
+    format!("{}:W{}:B{}", to_move, square_list(1), square_list(0))
+}
+
+/// Parses a move such as `11-15`, `11x18x25` or the shortened capture
+/// `11x25`, returning the engine squares it names.
+pub fn parse_move(token: &str) -> Result<Vec<usize>, PdnError> {
+    let separator = if token.contains('x') { 'x' } else { '-' };
+    let squares = token
+        .split(separator)
+        .map(parse_square)
+        .collect::<Result<Vec<usize>, PdnError>>()
+        .map_err(|_| PdnError::InvalidMove {
+            token: token.to_string(),
+        })?;
+    if squares.len() < 2 {
+        return Err(PdnError::InvalidMove {
+            token: token.to_string(),
+        });
+    }
+    Ok(squares)
+}
+
+/// Finds the legal move of `position` named by `token`. A capture may list
+/// only some of its landing squares as long as a single move matches.
+pub fn find_move(position: &Position, token: &str) -> Result<BitMove, PdnError> {
+    let squares = parse_move(token)?;
+    let matches: Vec<BitMove> = position
+        .legal_moves()
+        .into_iter()
+        .filter(|the_move| {
+            let path: Vec<usize> = the_move.path.iter().map(|&s| s as usize).collect();
+            path.first() == squares.first()
+                && path.last() == squares.last()
+                && is_subsequence(&squares, &path)
+        })
+        .collect();
+    match matches.len() {
+        0 => Err(PdnError::IllegalMove {
+            token: token.to_string(),
+        }),
+        1 => Ok(matches.into_iter().next().unwrap()),
+        _ => {
+            // a full path picks one move out of several with the same ends
+            let exact: Vec<&BitMove> = matches
+                .iter()
+                .filter(|the_move| {
+                    the_move
+                        .path
+                        .iter()
+                        .map(|&s| s as usize)
+                        .eq(squares.iter().cloned())
+                })
+                .collect();
+            match exact.as_slice() {
+                [the_move] => Ok((*the_move).clone()),
+                _ => Err(PdnError::AmbiguousMove {
+                    token: token.to_string(),
+                }),
+            }
+        }
+    }
+}
+
+fn is_subsequence(short: &[usize], long: &[usize]) -> bool {
+    let mut long = long.iter();
+    short.iter().all(|square| long.any(|other| other == square))
+}
+
+/// Writes the path of engine squares visited by a move in PDN
+pub fn path_to_pdn(path: &[usize]) -> String {
+    // rows two apart can only be a jump
+    let is_capture =
+        path.len() > 2 || (path[0] / 4).max(path[1] / 4) - (path[0] / 4).min(path[1] / 4) == 2;
+    let separator = if is_capture { "x" } else { "-" };
+    path.iter()
+        .map(|&square| square_to_pdn(square).to_string())
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+/// A game of a PDN file, its moves given as paths of engine squares
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PdnGame {
+    pub tags: Vec<(String, String)>,
+    pub moves: Vec<Vec<usize>>,
+    pub result: String,
+}
+
+impl PdnGame {
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The starting position, given by the FEN tag if there is one
+    pub fn start(&self) -> Result<Position, PdnError> {
+        match self.tag("FEN") {
+            Some(fen) => parse_fen(fen),
+            None => Ok(Position::new()),
+        }
+    }
+}
+
+fn is_result(token: &str) -> bool {
+    matches!(
+        token,
+        "1-0" | "0-1" | "2-0" | "0-2" | "1-1" | "1/2-1/2" | "0-0" | "*"
+    )
+}
+
+fn normalize_result(token: &str) -> String {
+    match token {
+        "2-0" => RESULT_PLAYER_1_WINS.to_string(),
+        "0-2" => RESULT_PLAYER_2_WINS.to_string(),
+        "1-1" => RESULT_DRAW.to_string(),
+        "0-0" => RESULT_UNKNOWN.to_string(),
+        other => other.to_string(),
+    }
+}
+
+enum Token {
+    Tag(String, String),
+    Move(String),
+    Result(String),
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, PdnError> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '[' {
+            let tag: String = chars.by_ref().skip(1).take_while(|&c| c != ']').collect();
+            let (name, value) = tag
+                .trim()
+                .split_once(char::is_whitespace)
+                .ok_or_else(|| PdnError::InvalidTag { tag: tag.clone() })?;
+            let value = value.trim();
+            if value.len() < 2 || !value.starts_with('"') || !value.ends_with('"') {
+                return Err(PdnError::InvalidTag { tag: tag.clone() });
+            }
+            tokens.push(Token::Tag(
+                name.to_string(),
+                value[1..value.len() - 1].to_string(),
+            ));
+        } else if c == '{' {
+            chars.by_ref().find(|&c| c == '}');
+        } else if c == '(' {
+            // variations are skipped
+            let mut depth = 0;
+            for c in chars.by_ref() {
+                match c {
+                    '(' => depth += 1,
+                    ')' if depth == 1 => break,
+                    ')' => depth -= 1,
+                    _ => {}
+                }
+            }
+        } else {
+            let mut word = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() || c == '{' || c == '(' || c == '[' {
+                    break;
+                }
+                word.push(c);
+                chars.next();
+            }
+            if is_result(&word) {
+                tokens.push(Token::Result(normalize_result(&word)));
+                continue;
+            }
+            // drop move numbers such as `12.` and annotations such as `!?`
+            let word = match word.rfind('.') {
+                Some(index) => &word[index + 1..],
+                None => &word[..],
+            };
+            let word = word.trim_end_matches(&['!', '?', '*'][..]);
+            if !word.is_empty() {
+                tokens.push(Token::Move(word.to_string()));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// Reads every game of a PDN text. The moves are checked against the rules
+/// and stored as complete capture paths.
+pub fn parse_games(text: &str) -> Result<Vec<PdnGame>, PdnError> {
+    let mut games = Vec::new();
+    let mut game = PdnGame {
+        tags: Vec::new(),
+        moves: Vec::new(),
+        result: RESULT_UNKNOWN.to_string(),
+    };
+    let mut position = Position::new();
+    let mut started = false;
+    for token in tokenize(text)? {
+        match token {
+            Token::Tag(name, value) => {
+                if !game.moves.is_empty() {
+                    games.push(game.clone());
+                    game.tags.clear();
+                    game.moves.clear();
+                    game.result = RESULT_UNKNOWN.to_string();
+                }
+                game.tags.push((name, value));
+                position = game.start()?;
+                started = true;
+            }
+            Token::Move(token) => {
+                let the_move = find_move(&position, &token)?;
+                position.apply(&the_move);
+                game.moves
+                    .push(the_move.path.iter().map(|&s| s as usize).collect());
+                started = true;
+            }
+            Token::Result(result) => {
+                game.result = result;
+                games.push(game.clone());
+                game.tags.clear();
+                game.moves.clear();
+                game.result = RESULT_UNKNOWN.to_string();
+                position = Position::new();
+                started = false;
+            }
+        }
+    }
+    if started {
+        games.push(game);
+    }
+    Ok(games)
+}
+
+/// Writes a game in PDN. A `Result` tag is added, or updated, to match the
+/// result of the game.
+pub fn write_game(game: &PdnGame) -> Result<String, PdnError> {
+    let mut text = String::new();
+    let mut has_result_tag = false;
+    for (name, value) in game.tags.iter() {
+        let value = if name == "Result" {
+            has_result_tag = true;
+            &game.result
+        } else {
+            value
+        };
+        text.push_str(&format!("[{} \"{}\"]\n", name, value));
+    }
+    if !has_result_tag {
+        text.push_str(&format!("[Result \"{}\"]\n", game.result));
+    }
+    text.push('\n');
+
+    let start = game.start()?;
+    let mut line = String::new();
+    let mut words = Vec::new();
+    for (ply, path) in game.moves.iter().enumerate() {
+        let ply = ply + start.to_move();
+        if ply % 2 == 0 {
+            words.push(format!("{}.", ply / 2 + 1));
+        } else if words.is_empty() {
+            words.push(format!("{}...", ply / 2 + 1));
+        }
+        words.push(path_to_pdn(path));
+    }
+    words.push(game.result.clone());
+    for word in words {
+        if !line.is_empty() && line.len() + word.len() + 1 > 79 {
+            text.push_str(&line);
+            text.push('\n');
+            line.clear();
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(&word);
+    }
+    text.push_str(&line);
+    text.push('\n');
+    Ok(text)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn square_numbering() {
+        for number in 1..=32 {
+            assert_eq!(number, square_to_pdn(pdn_to_square(number).unwrap()));
+        }
+        assert_eq!(None, pdn_to_square(0));
+        assert_eq!(None, pdn_to_square(33));
+
+        // the seven opening moves of Black
+        let mut openings: Vec<String> = Position::new()
+            .legal_moves()
+            .iter()
+            .map(|the_move| {
+                path_to_pdn(
+                    &the_move
+                        .path
+                        .iter()
+                        .map(|&s| s as usize)
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect();
+        openings.sort();
+        let expected = vec!["10-14", "10-15", "11-15", "11-16", "12-16", "9-13", "9-14"];
+        assert_eq!(expected, openings);
+    }
+
+    #[test]
+    fn fen_round_trip() {
+        let start = "B:W21,22,23,24,25,26,27,28,29,30,31,32:B1,2,3,4,5,6,7,8,9,10,11,12";
+        assert_eq!(Position::new(), parse_fen(start).unwrap());
+        assert_eq!(start, to_fen(&Position::new()));
+        assert_eq!(Position::new(), parse_fen("B:W21-32:B1-12").unwrap());
+
+        let fen = "W:W18,K30:B5,K14";
+        let position = parse_fen(fen).unwrap();
+        assert_eq!(1, position.to_move());
+        assert_eq!(1, position.kings(0).count_ones());
+        assert_eq!(fen, to_fen(&position));
+
+        assert!(parse_fen("X:W18:B5").is_err());
+        assert!(parse_fen("B:W18:B18").is_err());
+        // ranges are checked before they are walked
+        assert!(parse_fen("W:W1-18446744073709551615:B5").is_err());
+        assert!(parse_fen("W:W30-33:B5").is_err());
+        assert!(parse_fen("W:W8-5:B1").is_err());
+    }
+
+    #[test]
+    fn shortened_capture_is_resolved() {
+        // Black man on 11 jumps White men on 15 and 23 (11x18x27)
+        let position = parse_fen("B:W15,23,32:B11").unwrap();
+        let the_move = find_move(&position, "11x27").unwrap();
+        assert_eq!(3, the_move.path.len());
+        assert_eq!(the_move, find_move(&position, "11x18x27").unwrap());
+        assert_eq!(
+            Err(PdnError::IllegalMove {
+                token: "11-15".to_string()
+            }),
+            find_move(&position, "11-15")
+        );
+    }
+
+    #[test]
+    fn read_and_write_games() {
+        let text = r#"
+[Event "Test"]
+[Black "A"]
+[White "B"]
+[Result "0-1"]
+1. 11-15 22-18 {a comment} 2. 15x22 25x18 (2... 26x17) 3. 8-11 29-25 0-1
+
+[Event "Second"]
+[FEN "W:W18:B14"]
+1... 18x9 *
+"#;
+        let games = parse_games(text).unwrap();
+        assert_eq!(2, games.len());
+        assert_eq!(Some("Test"), games[0].tag("Event"));
+        assert_eq!(6, games[0].moves.len());
+        assert_eq!(RESULT_PLAYER_2_WINS, games[0].result);
+        assert_eq!(1, games[1].moves.len());
+        assert_eq!(RESULT_UNKNOWN, games[1].result);
+
+        let written = write_game(&games[0]).unwrap();
+        assert!(written.contains("1. 11-15 22-18 2. 15x22 25x18 3. 8-11 29-25 0-1"));
+        assert_eq!(games[0], parse_games(&written).unwrap()[0]);
+
+        let written = write_game(&games[1]).unwrap();
+        assert!(written.contains("[Result \"*\"]"));
+        assert!(written.contains("1... 18x9 *"));
+        assert_eq!(games[1].moves, parse_games(&written).unwrap()[0].moves);
+    }
+}
@@ -0,0 +1,200 @@
+use super::{BoardPosition, Game, SimpleMove};
+
+/// Every distinct move available in `game`, as the path of the moving
+/// piece. Capture sequences landing on the same square after taking the
+/// same pieces, such as a king going round a loop both ways, are one move
+/// and only the first of their paths is kept.
+pub fn legal_paths(game: &Game) -> Vec<Vec<BoardPosition>> {
+    let jump_moves = game.get_available_jump_moves();
+    if jump_moves.is_empty() {
+        return game
+            .get_available_simple_moves()
+            .iter()
+            .map(|simple_move| {
+                vec![
+                    BoardPosition::new(simple_move.from_row(), simple_move.from_column()),
+                    BoardPosition::new(simple_move.to_row(), simple_move.to_column()),
+                ]
+            })
+            .collect();
+    }
+
+    let mut seen = Vec::new();
+    let mut paths = Vec::new();
+    for path in jump_moves
+        .iter()
+        .flat_map(|jump_move| jump_move.sequences())
+    {
+        let mut captured = game.captured_positions(&path);
+        captured.sort_by_key(|position| (position.row, position.column));
+        let key = (path[0], *path.last().unwrap(), captured);
+        if !seen.contains(&key) {
+            seen.push(key);
+            paths.push(path);
+        }
+    }
+    paths
+}
+
+// plays a path returned by `legal_paths`
+fn play(game: &mut Game, path: &[BoardPosition]) {
+    let result = if game.get_available_jump_moves().is_empty() {
+        game.apply_simple_move(SimpleMove::new(
+            path[0].row,
+            path[0].column,
+            path[1].row,
+            path[1].column,
+        ))
+    } else {
+        game.apply_jump_move(path.to_vec())
+    };
+    result.expect("legal paths can be played");
+}
+
+/// Counts the move sequences of `depth` plies from the current position of
+/// `game`, which is left as it was. Draws by repetition or by the move
+/// limit do not stop the count, only a player without moves does.
+pub fn perft(game: &mut Game, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let paths = legal_paths(game);
+    if depth == 1 {
+        return paths.len() as u64;
+    }
+    let mut nodes = 0;
+    for path in paths.iter() {
+        play(game, path);
+        nodes += perft(game, depth - 1);
+        game.undo_move();
+    }
+    nodes
+}
+
+/// `perft` split by first move, to find which move a wrong count comes from
+pub fn divide(game: &mut Game, depth: u32) -> Vec<(Vec<BoardPosition>, u64)> {
+    let mut counts = Vec::new();
+    for path in legal_paths(game) {
+        play(game, &path);
+        let nodes = perft(game, depth.saturating_sub(1));
+        game.undo_move();
+        counts.push((path, nodes));
+    }
+    counts
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::bitboard::{position_to_square, Position};
+    use super::super::pdn;
+    use super::super::Rules;
+    use super::*;
+
+    fn game_from_fen(fen: &str) -> Game {
+        let position = pdn::parse_fen(fen).unwrap();
+        let mut game = Game::new();
+        game.set_board(position.to_board());
+        game.set_current_player(position.to_move());
+        game.find_available_moves();
+        game
+    }
+
+    // the same count over the bitboard position, an independent move
+    // generator
+    fn position_perft(position: &Position, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let mut moves = position.legal_moves();
+        moves.sort_by_key(|the_move| (the_move.from(), the_move.to(), the_move.captured));
+        moves.dedup_by_key(|the_move| (the_move.from(), the_move.to(), the_move.captured));
+        moves
+            .iter()
+            .map(|the_move| position_perft(&position.after(the_move), depth - 1))
+            .sum()
+    }
+
+    #[test]
+    fn english_initial_position() {
+        let expected = [1, 7, 49, 302, 1469, 7361, 36768, 179740];
+        let mut game = Game::new();
+        for (depth, &nodes) in expected.iter().enumerate() {
+            assert_eq!(nodes, perft(&mut game, depth as u32), "depth {}", depth);
+        }
+        assert!(game.history().is_empty());
+        assert_eq!(Position::new(), Position::from_board(game.board(), 0));
+    }
+
+    #[test]
+    fn international_initial_position() {
+        let expected = [1, 9, 81, 658, 4265, 27117];
+        let mut game = Game::with_rules(Rules::international());
+        for (depth, &nodes) in expected.iter().enumerate() {
+            assert_eq!(nodes, perft(&mut game, depth as u32), "depth {}", depth);
+        }
+    }
+
+    #[test]
+    fn king_loop_is_one_move() {
+        // the black king on 11 can take the four men round the loop
+        // either way, ending back on 11
+        let mut game = game_from_fen("B:W6,7,14,15:BK11");
+        let paths = legal_paths(&game);
+        assert_eq!(1, paths.len());
+        assert_eq!(5, paths[0].len());
+        assert_eq!(paths[0][0], paths[0][4]);
+        assert_eq!(1, perft(&mut game, 1));
+        assert_eq!(0, perft(&mut game, 2));
+    }
+
+    #[test]
+    fn divide_adds_up_to_perft() {
+        let mut game = Game::new();
+        let counts = divide(&mut game, 4);
+        assert_eq!(7, counts.len());
+        let total: u64 = counts.iter().map(|(_, nodes)| nodes).sum();
+        assert_eq!(perft(&mut game, 4), total);
+        assert_eq!(1469, total);
+    }
+
+    #[test]
+    fn tricky_positions_match_the_bitboard() {
+        let positions = [
+            // kings on both sides with multi-jumps and crowning captures
+            "B:W18,24,27,28,K10,K15:B12,16,20,K22,K25,K29",
+            // men about to crown, one of them by a capture
+            "W:W6,7,9,K31:B2,3,14,23,26,K8",
+            // a capture where the shortened path is not allowed
+            "B:W10,11,19,20,27,28:B1,2,6,K15",
+            // a crowded middle game
+            "W:W13,17,18,21,22,25,26,27,29,30,31,32:B1,2,3,4,5,6,7,8,9,10,11,15",
+        ];
+        for fen in positions.iter() {
+            let mut game = game_from_fen(fen);
+            let position = pdn::parse_fen(fen).unwrap();
+            for depth in 1..=5 {
+                assert_eq!(
+                    position_perft(&position, depth),
+                    perft(&mut game, depth),
+                    "{} at depth {}",
+                    fen,
+                    depth
+                );
+            }
+            assert_eq!(
+                position,
+                Position::from_board(game.board(), position.to_move())
+            );
+        }
+    }
+
+    #[test]
+    fn paths_start_on_the_moving_piece() {
+        let game = game_from_fen("W:W6,7,9,K31:B2,3,14,23,26,K8");
+        let position = pdn::parse_fen("W:W6,7,9,K31:B2,3,14,23,26,K8").unwrap();
+        for path in legal_paths(&game) {
+            let square = position_to_square(&path[0]);
+            assert_eq!(Some(1), position.piece_at(square).map(|(player, _)| player));
+        }
+    }
+}
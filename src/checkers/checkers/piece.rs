@@ -1,5 +1,6 @@
 use super::player::Player;
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum PieceType {
     Man,
     King,
@@ -1,3 +1,4 @@
+#[derive(Clone)]
 pub struct Player {
     pub id: u32,
 }
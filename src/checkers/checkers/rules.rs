@@ -0,0 +1,101 @@
+/// The rules of a draughts variant. Pieces always stand on the dark tiles
+/// and player 1 always moves first, from the rows with the lowest index.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Rules {
+    /// Number of rows and columns of the board
+    pub board_size: usize,
+
+    /// Rows initially filled with men for each player
+    pub rows_of_men: usize,
+
+    /// Men may capture backwards as well as forwards
+    pub men_capture_backwards: bool,
+
+    /// Kings move and capture along whole diagonals
+    pub flying_kings: bool,
+
+    /// The capture sequence taking the most pieces is mandatory
+    pub max_capture: bool,
+
+    /// Men are allowed to capture kings
+    pub men_capture_kings: bool,
+
+    /// A man reaching the last row in the middle of a capture is crowned
+    /// and carries on capturing as a king
+    pub crown_during_capture: bool,
+
+    /// Italian tie-breaks between captures of the same length: capturing
+    /// with a king, capturing the most kings, and capturing kings first
+    pub capture_quality: bool,
+}
+
+impl Rules {
+    /// English draughts, also known as American checkers
+    pub fn english() -> Rules {
+        Rules {
+            board_size: 8,
+            rows_of_men: 3,
+            men_capture_backwards: false,
+            flying_kings: false,
+            max_capture: false,
+            men_capture_kings: true,
+            crown_during_capture: false,
+            capture_quality: false,
+        }
+    }
+
+    /// International draughts, played on a 10x10 board
+    pub fn international() -> Rules {
+        Rules {
+            board_size: 10,
+            rows_of_men: 4,
+            men_capture_backwards: true,
+            flying_kings: true,
+            max_capture: true,
+            men_capture_kings: true,
+            crown_during_capture: false,
+            capture_quality: false,
+        }
+    }
+
+    /// International rules on an 8x8 board
+    pub fn brazilian() -> Rules {
+        Rules {
+            board_size: 8,
+            rows_of_men: 3,
+            ..Rules::international()
+        }
+    }
+
+    /// Russian draughts: any capture may be chosen, and men crowned during a
+    /// capture go on as kings
+    pub fn russian() -> Rules {
+        Rules {
+            max_capture: false,
+            crown_during_capture: true,
+            ..Rules::brazilian()
+        }
+    }
+
+    /// Italian draughts. The Italian board is the mirror image of the one
+    /// used here, which does not change the game.
+    pub fn italian() -> Rules {
+        Rules {
+            max_capture: true,
+            men_capture_kings: false,
+            capture_quality: true,
+            ..Rules::english()
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Rules> {
+        match name {
+            "english" => Some(Rules::english()),
+            "international" => Some(Rules::international()),
+            "brazilian" => Some(Rules::brazilian()),
+            "russian" => Some(Rules::russian()),
+            "italian" => Some(Rules::italian()),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,256 @@
+use super::bitboard::{BitMove, Position};
+use std::time::{Duration, Instant};
+
+/// Score of a won position, reduced by the number of plies needed to win
+/// so that faster wins are preferred
+pub const WIN_SCORE: i32 = 100_000;
+
+/// Back rows of each player, kept occupied to stop the opponent crowning
+const BACK_ROWS: [u32; 2] = [0x0000_000F, 0xF000_0000];
+
+/// Weights of the terms of the static evaluation
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Weights {
+    pub man: i32,
+    pub king: i32,
+    /// Bonus for every man still on its own back row
+    pub back_rank: i32,
+    /// Bonus for every simple move available
+    pub mobility: i32,
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Weights {
+            man: 100,
+            king: 160,
+            back_rank: 10,
+            mobility: 2,
+        }
+    }
+}
+
+impl Weights {
+    /// Static evaluation from the point of view of the player to move
+    pub fn evaluate(&self, position: &Position) -> i32 {
+        let me = position.to_move();
+        self.evaluate_player(position, me) - self.evaluate_player(position, 1 - me)
+    }
+
+    fn evaluate_player(&self, position: &Position, player: usize) -> i32 {
+        let men = position.men(player);
+        let mut score = self.man * men.count_ones() as i32
+            + self.king * position.kings(player).count_ones() as i32
+            + self.back_rank * (men & BACK_ROWS[player]).count_ones() as i32;
+        if self.mobility != 0 {
+            let moves = position.with_to_move(player).simple_moves().len();
+            score += self.mobility * moves as i32;
+        }
+        score
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchResult {
+    pub best_move: Option<BitMove>,
+    /// Score of the best move for the player to move
+    pub score: i32,
+    /// Last depth searched to completion
+    pub depth: u32,
+    pub nodes: u64,
+}
+
+/// Iterative deepening alpha-beta search. Captures are always searched to
+/// the end, past the depth limit, so that the evaluation is only applied
+/// to quiet positions. Draws by repetition or by the move limit are not
+/// taken into account.
+pub struct Searcher {
+    weights: Weights,
+    deadline: Option<Instant>,
+    nodes: u64,
+    stopped: bool,
+}
+
+impl Searcher {
+    pub fn new(weights: Weights) -> Searcher {
+        Searcher {
+            weights,
+            deadline: None,
+            nodes: 0,
+            stopped: false,
+        }
+    }
+
+    /// Searches `position` up to `depth` plies, stopping early once
+    /// `time_limit` is over. The result of the deepest completed iteration
+    /// is returned, at least one move is returned whenever there is one.
+    pub fn search(
+        &mut self,
+        position: &Position,
+        depth: u32,
+        time_limit: Option<Duration>,
+    ) -> SearchResult {
+        self.deadline = time_limit.map(|limit| Instant::now() + limit);
+        self.nodes = 0;
+        self.stopped = false;
+
+        let mut moves = position.legal_moves();
+        let mut result = SearchResult {
+            best_move: moves.first().cloned(),
+            score: if moves.is_empty() { -WIN_SCORE } else { 0 },
+            depth: 0,
+            nodes: 0,
+        };
+        if moves.len() < 2 {
+            result.score = match moves.first() {
+                Some(the_move) => {
+                    -self.negamax(&position.after(the_move), depth, 1, -WIN_SCORE, WIN_SCORE)
+                }
+                None => result.score,
+            };
+            result.depth = depth;
+            result.nodes = self.nodes;
+            return result;
+        }
+
+        for current_depth in 1..=depth.max(1) {
+            let mut alpha = -WIN_SCORE;
+            let mut best = 0;
+            for (index, the_move) in moves.iter().enumerate() {
+                let score = -self.negamax(
+                    &position.after(the_move),
+                    current_depth - 1,
+                    1,
+                    -WIN_SCORE,
+                    -alpha,
+                );
+                if self.stopped {
+                    break;
+                }
+                if score > alpha || index == 0 {
+                    alpha = score;
+                    best = index;
+                }
+            }
+            if self.stopped {
+                break;
+            }
+            // search the best move first in the next iteration
+            moves.swap(0, best);
+            result.best_move = Some(moves[0].clone());
+            result.score = alpha;
+            result.depth = current_depth;
+        }
+        result.nodes = self.nodes;
+        result
+    }
+
+    fn out_of_time(&mut self) -> bool {
+        if !self.stopped && self.nodes % 1024 == 0 {
+            if let Some(deadline) = self.deadline {
+                self.stopped = Instant::now() >= deadline;
+            }
+        }
+        self.stopped
+    }
+
+    fn negamax(
+        &mut self,
+        position: &Position,
+        depth: u32,
+        ply: i32,
+        mut alpha: i32,
+        beta: i32,
+    ) -> i32 {
+        self.nodes += 1;
+        if self.out_of_time() {
+            return 0;
+        }
+
+        let moves = position.legal_moves();
+        let first_move = match moves.first() {
+            Some(the_move) => the_move,
+            None => return ply - WIN_SCORE,
+        };
+        if depth == 0 && !first_move.is_capture() {
+            return self.weights.evaluate(position);
+        }
+
+        for the_move in moves.iter() {
+            let score = -self.negamax(
+                &position.after(the_move),
+                depth.saturating_sub(1),
+                ply + 1,
+                -beta,
+                -alpha,
+            );
+            if self.stopped {
+                return 0;
+            }
+            if score > alpha {
+                alpha = score;
+                if alpha >= beta {
+                    break;
+                }
+            }
+        }
+        alpha
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn bit(square: u8) -> u32 {
+        1 << square
+    }
+
+    #[test]
+    fn prefers_the_longer_capture() {
+        // both men can take a single piece or two pieces in a row
+        let position = Position::from_masks(
+            [bit(9) | bit(10), bit(13) | bit(14) | bit(22) | bit(31)],
+            [0, 0],
+            0,
+        );
+        let result = Searcher::new(Weights::default()).search(&position, 1, None);
+        let best_move = result.best_move.unwrap();
+        assert_eq!(2, best_move.captured.count_ones());
+    }
+
+    #[test]
+    fn finds_a_won_ending() {
+        // the king on 9 jumps the last enemy man on 13
+        let position = Position::from_masks([0, bit(13)], [bit(9), 0], 0);
+        let result = Searcher::new(Weights::default()).search(&position, 4, None);
+        assert_eq!(vec![9, 18], result.best_move.unwrap().path);
+        assert!(result.score > WIN_SCORE - 10);
+    }
+
+    #[test]
+    fn lost_position_has_no_move() {
+        let position = Position::from_masks([0, bit(13)], [0, 0], 0);
+        let result = Searcher::new(Weights::default()).search(&position, 4, None);
+        assert_eq!(None, result.best_move);
+        assert_eq!(-WIN_SCORE, result.score);
+    }
+
+    #[test]
+    fn time_limit_stops_the_search() {
+        let start = Instant::now();
+        let result = Searcher::new(Weights::default()).search(
+            &Position::new(),
+            64,
+            Some(Duration::from_millis(50)),
+        );
+        assert!(start.elapsed() < Duration::from_secs(2));
+        assert!(result.best_move.is_some());
+        assert!(result.depth < 64);
+    }
+
+    #[test]
+    fn evaluation_is_symmetric_at_start() {
+        assert_eq!(0, Weights::default().evaluate(&Position::new()));
+    }
+}
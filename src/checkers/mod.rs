@@ -1,15 +1,25 @@
 pub mod python;
 
+use std::fmt;
 use std::io::stdout;
+use std::sync::Arc;
+use std::time::Duration;
 
 mod checkers;
 
 use checkers::{
-    Board, BoardPosition, EmptyTile, Game, GameState, JumpMove, KingPiece, ManPiece, MoveError,
-    OccupiedTile, SimpleMove, Tile,
+    Board, BoardPosition, EmptyTile, Game, GameState, KingPiece, ManPiece, MoveError, OccupiedTile,
+    Rules, SimpleMove, Tile,
 };
 
+use crate::checkers::checkers::bitboard::Position;
+use crate::checkers::checkers::endgame::{Database, Value};
+use crate::checkers::checkers::pdn::{self, PdnError, PdnGame};
+use crate::checkers::checkers::perft;
+use crate::checkers::checkers::search::{Searcher, Weights};
 use crate::checkers::checkers::PieceType;
+use crate::mcts::MctsGame;
+use crate::symmetry::Symmetry;
 
 mod util;
 
@@ -24,7 +34,14 @@ fn apply_positions_as_move(
         let row_diff = util::absolute_diff(start.row, end.row);
         let col_diff = util::absolute_diff(start.column, end.column);
 
-        if row_diff == 1 && col_diff == 1 {
+        // flying kings make long simple moves, so anything that is not one
+        // of the available captures is tried as a simple move
+        let is_jump = game
+            .get_available_jump_moves()
+            .iter()
+            .any(|jump_move| jump_move.contains_complete_jump_sequence(&positions));
+
+        if (row_diff == 1 && col_diff == 1) || (row_diff == col_diff && !is_jump) {
             game.apply_simple_move(SimpleMove::new(
                 start.row,
                 start.column,
@@ -59,6 +76,50 @@ fn player_color_to_name(color: PlayerColor) -> &'static str {
     }
 }
 
+/// Reasons for `set_state` to refuse a state
+#[derive(Debug, PartialEq, Eq)]
+enum StateError {
+    InvalidPlayer { player: u8 },
+    InvalidSize { rows: usize, columns: usize },
+    InvalidPiece { row: usize, column: usize, code: u8 },
+    LightSquare { row: usize, column: usize },
+    TooManyPieces { player: u8, count: usize },
+    ManOnCrowningRow { row: usize, column: usize },
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StateError::InvalidPlayer { player } => {
+                write!(f, "invalid player to move {}, expected 0 or 1", player)
+            }
+            StateError::InvalidSize { rows, columns } => {
+                write!(f, "invalid board size {}x{}", rows, columns)
+            }
+            StateError::InvalidPiece { row, column, code } => write!(
+                f,
+                "invalid piece {} at row {} column {}, expected 0-4",
+                code, row, column
+            ),
+            StateError::LightSquare { row, column } => {
+                write!(
+                    f,
+                    "piece on a light square at row {} column {}",
+                    row, column
+                )
+            }
+            StateError::TooManyPieces { player, count } => {
+                write!(f, "player {} has too many pieces ({})", player, count)
+            }
+            StateError::ManOnCrowningRow { row, column } => write!(
+                f,
+                "man on its crowning row at row {} column {}",
+                row, column
+            ),
+        }
+    }
+}
+
 struct IntBoardValues {
     empty: u8,
     man_1: u8,
@@ -67,98 +128,596 @@ struct IntBoardValues {
     king_2: u8,
 }
 
+/// Number of playable (dark) squares on the 8x8 board
+#[allow(dead_code)]
+const NUMBER_SQUARES: usize = 32;
+
+// Actions
+// ---------------------------------------------------------
+// Squares are numbered 0-31 row by row starting from player 1's back row,
+// `square = row * 4 + column / 2`. A move is the path of squares visited by
+// the moving piece, and its action is its first hop, `from * 32 + to` as it
+// always was, above which `k` tells apart the capture paths starting with
+// the same hop:
+//
+//     action = from * 32 + to + 1024 * k
+//
+// `k` is the rank of the move among the legal moves sharing its first hop,
+// ordered by landing square and then by the squares of the pieces taken,
+// all of them seen from the side of the player to move: square `s` is
+// `31 - s` for player 2. Simple moves, single jumps and the captures alone
+// to start with their hop have `k = 0`, so `action % 1024` is always the
+// first hop, and `k` stays the same when the board is rotated for the other
+// player. For example the double jump 9 -> 18 -> 27 is `9 * 32 + 18` unless
+// another capture starts with the hop 9 -> 18.
+//
+// Other board sizes use the same scheme with `N = size * size / 2` squares
+// in place of 32, e.g. `from * 50 + to` on the 10x10 international board.
+
+fn square_to_position(square: usize, size: usize) -> BoardPosition {
+    let row = square / (size / 2);
+    BoardPosition::new(row, (square % (size / 2)) * 2 + row % 2)
+}
+
+fn position_to_square(position: &BoardPosition, size: usize) -> usize {
+    position.row * (size / 2) + position.column / 2
+}
+
+// the pieces a capture path takes, in a fixed order
+fn sorted_captures(game: &Game, path: &[BoardPosition]) -> Vec<BoardPosition> {
+    let mut captured = game.captured_positions(path);
+    captured.sort_by_key(|position| (position.row, position.column));
+    captured
+}
+
+#[derive(Clone)]
 struct Checkers {
     game: Game,
+    /// Observations and the legal action mask are given from the point of
+    /// view of the player to move, rotating the board for player 2.
+    perspective: bool,
+    /// FEN of the starting position when it is not the initial one
+    start_fen: Option<String>,
+    /// Result read by `load_pdn` and the actions it was read with, for
+    /// games that did not end on the board such as resignations
+    loaded_result: Option<(String, Vec<usize>)>,
+    /// Scale of the shaped reward given for every change of the material
+    /// difference, zero for terminal rewards only
+    material_reward: f32,
+    /// Endgame database probed for positions with few pieces
+    endgame: Option<Arc<Database>>,
+    /// Ends the game as soon as the endgame database knows its result
+    adjudicate: bool,
+}
+
+/// Why a game has finished
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Termination {
+    /// The loser has no pieces left
+    NoPieces,
+    /// The loser has pieces but none of them can move
+    Blocked,
+    /// Threefold repetition or move limit
+    Draw,
+    /// The endgame database knows the result, given for the player to move
+    Endgame(Value),
+}
+
+impl Termination {
+    fn name(&self) -> &'static str {
+        match self {
+            Termination::NoPieces => "no_pieces",
+            Termination::Blocked => "blocked",
+            Termination::Draw => "draw",
+            Termination::Endgame(_) => "endgame",
+        }
+    }
+}
+
+/// Value of a king for the shaped reward, men being worth 1
+const KING_MATERIAL: f32 = 1.5;
+
+struct StepResult {
+    observation: Vec<Vec<Vec<u8>>>,
+    /// Reward of each player, indexed by `to_play`
+    rewards: [f32; 2],
+    done: bool,
+    /// `to_play` index of the winner, `None` for a draw or a game in progress
+    winner: Option<u8>,
+    termination: Option<Termination>,
 }
 
 impl Checkers {
-    fn new() -> Self {
-        Checkers { game: Game::new() }
-    }
-
-    fn action_to_positions(&self, action: &usize) -> Vec<BoardPosition> {
-        let mut positions = Vec::new();
-        let from = action / 32;
-        let to = action % 32;
-        let x0 = from / 4;
-        let y0 = from % 4;
-        positions.push(BoardPosition::new(
-            x0,
-            y0 * 2 + if x0 % 2 == 0 { 0 } else { 1 },
-        ));
-        let x1 = to / 4;
-        let y1 = to % 4;
-        positions.push(BoardPosition::new(
-            x1,
-            y1 * 2 + if x1 % 2 == 0 { 0 } else { 1 },
-        ));
-        positions
-    }
-
-    fn positions_to_action(&self, positions: &Vec<BoardPosition>) -> usize {
-        let from = positions[0].row * 4
-            + (positions[0].column - if positions[0].row % 2 == 0 { 0 } else { 1 }) / 2;
-        let to = positions[1].row * 4
-            + (positions[1].column - if positions[1].row % 2 == 0 { 0 } else { 1 }) / 2;
-        from * 32 + to
-    }
-
-    fn simple_move_to_action(&self, simple_move: &SimpleMove) -> usize {
-        let mut positions = Vec::new();
-        positions.push(BoardPosition::new(
-            simple_move.from_row(),
-            simple_move.from_column(),
-        ));
-        positions.push(BoardPosition::new(
-            simple_move.to_row(),
-            simple_move.to_column(),
-        ));
-        self.positions_to_action(&positions)
+    fn new(perspective: bool, draw_move_limit: Option<usize>, rules: Rules) -> Self {
+        let mut game = Game::with_rules(rules);
+        game.set_draw_move_limit(draw_move_limit);
+        Checkers {
+            game,
+            perspective,
+            start_fen: None,
+            loaded_result: None,
+            material_reward: 0.0,
+            endgame: None,
+            adjudicate: false,
+        }
     }
 
-    fn jump_move_to_action(&self, jump_move: &JumpMove) -> usize {
-        let mut positions = Vec::new();
-        positions.push(BoardPosition::new(
-            jump_move.from_row(),
-            jump_move.from_column(),
-        ));
-        positions.push(BoardPosition::new(
-            jump_move.get_jumps()[0].from_row(),
-            jump_move.get_jumps()[0].from_column(),
-        ));
-        self.positions_to_action(&positions)
+    /// Sets the endgame database, with `adjudicate` the game ends as soon
+    /// as a position in the database is reached
+    fn set_endgame(&mut self, database: Option<Arc<Database>>, adjudicate: bool) {
+        self.endgame = database;
+        self.adjudicate = adjudicate;
     }
 
-    fn step(&mut self, action: usize) -> (Vec<Vec<Vec<u8>>>, f32, bool) {
-        let positions = self.action_to_positions(&action);
-        let move_result = apply_positions_as_move(&mut self.game, positions);
-        let reward = match move_result {
-            Ok(game_state) => match game_state {
-                GameState::InProgress => 0f32,
-                GameState::GameOver { winner_id: _ } => 1f32,
-            },
-            Err(e) => match e {
-                MoveError::InvalidMove => panic!("\n *** Illegal move"),
-                MoveError::ShouldHaveJumped => panic!("\n *** Must take jump"),
-            },
+    /// Value of the current position for the player to move, `None` without
+    /// a database or when there are too many pieces left
+    fn probe_endgame(&self) -> Option<Value> {
+        match &self.endgame {
+            Some(database) if self.is_english() => database.probe(&self.position()),
+            _ => None,
+        }
+    }
+
+    fn set_material_reward(&mut self, material_reward: f32) {
+        self.material_reward = material_reward;
+    }
+
+    // men and kings of a player, kings counting `KING_MATERIAL`
+    fn material(&self, player_id: u32) -> f32 {
+        let board = self.game.board();
+        let mut material = 0.0;
+        for r in 0..board.number_rows() {
+            for c in 0..board.number_columns() {
+                if let Some(piece) = board.get_tile(r, c).get_piece() {
+                    if piece.get_player_id() == player_id {
+                        material += match piece.get_type() {
+                            PieceType::Man => 1.0,
+                            PieceType::King => KING_MATERIAL,
+                        };
+                    }
+                }
+            }
+        }
+        material
+    }
+
+    // material difference from the point of view of player 1
+    fn material_difference(&self) -> f32 {
+        self.material(1) - self.material(2)
+    }
+
+    fn termination(&self) -> Option<Termination> {
+        if !self.game.is_game_over() {
+            if self.adjudicate {
+                self.probe_endgame().map(Termination::Endgame)
+            } else {
+                None
+            }
+        } else if self.game.is_draw() {
+            Some(Termination::Draw)
+        } else if self.material(self.game.current_player().id) == 0.0 {
+            Some(Termination::NoPieces)
+        } else {
+            Some(Termination::Blocked)
+        }
+    }
+
+    /// `to_play` index of the winner of a finished game, `None` for a draw
+    /// or a game in progress. The player left without moves loses.
+    fn winner(&self) -> Option<u8> {
+        match self.termination() {
+            Some(Termination::NoPieces)
+            | Some(Termination::Blocked)
+            | Some(Termination::Endgame(Value::Loss(_))) => Some(1 - self.to_play()),
+            Some(Termination::Endgame(Value::Win(_))) => Some(self.to_play()),
+            _ => None,
+        }
+    }
+
+    fn board_size(&self) -> usize {
+        self.game.rules().board_size
+    }
+
+    /// Number of playable squares, 32 on the 8x8 board
+    fn number_squares(&self) -> usize {
+        self.board_size() * self.board_size() / 2
+    }
+
+    /// Maps an action to the same move seen from the other side of the
+    /// board. Rotating the board by 180 degrees maps the square `s` to
+    /// `N - 1 - s` and keeps the rank `k` of the move, so applying it twice
+    /// gives back the original action.
+    fn flip_action(&self, action: usize) -> usize {
+        let first_hops = self.number_squares() * self.number_squares();
+        let first_hop = action % first_hops;
+        action - first_hop + first_hops - 1 - first_hop
+    }
+
+    fn is_flipped(&self) -> bool {
+        self.perspective && self.game.current_player().id == 2
+    }
+
+    /// The observation and the policy over first hops, laid out as
+    /// `legal_action_mask`, of the same position with the colours swapped:
+    /// the board rotated by 180 degrees, the pieces of each player handed
+    /// to the other and the other player to move. Seen from the player to
+    /// move nothing changes but the side to move plane.
+    fn colour_flipped(
+        &self,
+        observation: &[Vec<Vec<u8>>],
+        policy: &[f32],
+    ) -> (Vec<Vec<Vec<u8>>>, Vec<f32>) {
+        if self.perspective {
+            let mut planes = observation.to_vec();
+            for row in planes[4].iter_mut() {
+                for item in row.iter_mut() {
+                    *item = 1 - *item;
+                }
+            }
+            return (planes, policy.to_vec());
+        }
+        let planes = vec![
+            Symmetry::Rotate180.transform_grid(&observation[1]),
+            Symmetry::Rotate180.transform_grid(&observation[0]),
+        ];
+        let mut flipped = vec![0.0; policy.len()];
+        for (first_hop, probability) in policy.iter().enumerate() {
+            flipped[self.flip_action(first_hop)] = *probability;
+        }
+        (planes, flipped)
+    }
+
+    /// Every symmetric equivalent of an observation and a policy over
+    /// first hops, the pair itself first: checkers only has the colour
+    /// flip.
+    fn symmetric_samples(
+        &self,
+        observation: &[Vec<Vec<u8>>],
+        policy: &[f32],
+    ) -> Vec<(Vec<Vec<Vec<u8>>>, Vec<f32>)> {
+        vec![
+            (observation.to_vec(), policy.to_vec()),
+            self.colour_flipped(observation, policy),
+        ]
+    }
+
+    /// The state, laid out as in `get_state`, with player 1 to move,
+    /// swapping the colours when player 2 is to move, and whether they were
+    /// swapped. Actions of the swapped position map back with
+    /// `flip_action`.
+    fn canonical_state(&self) -> ((u8, Vec<Vec<u8>>), bool) {
+        let (to_play, board, _) = self.get_state();
+        if to_play == 0 {
+            return ((to_play, board), false);
+        }
+        let board = Symmetry::Rotate180
+            .transform_grid(&board)
+            .into_iter()
+            .map(|row| {
+                row.into_iter()
+                    .map(|code| match code {
+                        0 => 0,
+                        // men and kings of one player become the other's
+                        code if code <= 2 => code + 2,
+                        code => code - 2,
+                    })
+                    .collect()
+            })
+            .collect();
+        ((0, board), true)
+    }
+
+    /// A `from * 32 + to` indexed mask over the first hop of the legal
+    /// actions (`action % 1024`), expressed in the same frame as the
+    /// observation.
+    fn legal_action_mask(&self) -> Vec<bool> {
+        let first_hops = self.number_squares() * self.number_squares();
+        let mut mask = vec![false; first_hops];
+        for action in self.legal_actions() {
+            let action = if self.is_flipped() {
+                self.flip_action(action)
+            } else {
+                action
+            };
+            mask[action % first_hops] = true;
+        }
+        mask
+    }
+
+    /// The legal moves, as paths of the moving piece, with their actions
+    fn legal_moves(&self) -> Vec<(usize, Vec<BoardPosition>)> {
+        let number_squares = self.number_squares();
+        // squares seen from the side of the player to move
+        let square = |position: &BoardPosition| {
+            let square = position_to_square(position, self.board_size());
+            if self.to_play() == 0 {
+                square
+            } else {
+                number_squares - 1 - square
+            }
         };
-        (self.get_observation(), reward, self.game.is_game_over())
+        let mut moves: Vec<(Vec<usize>, Vec<BoardPosition>)> = perft::legal_paths(&self.game)
+            .into_iter()
+            .map(|path| {
+                let mut captured: Vec<usize> = sorted_captures(&self.game, &path)
+                    .iter()
+                    .map(square)
+                    .collect();
+                captured.sort_unstable();
+                let mut key = vec![square(&path[0]), square(&path[1])];
+                key.push(square(path.last().unwrap()));
+                key.append(&mut captured);
+                (key, path)
+            })
+            .collect();
+        moves.sort_by(|(key, _), (other, _)| key.cmp(other));
+
+        let mut legal_moves = Vec::with_capacity(moves.len());
+        let mut rank = 0;
+        for (i, (key, path)) in moves.iter().enumerate() {
+            rank = if i > 0 && moves[i - 1].0[..2] == key[..2] {
+                rank + 1
+            } else {
+                0
+            };
+            let first_hop = position_to_square(&path[0], self.board_size()) * number_squares
+                + position_to_square(&path[1], self.board_size());
+            legal_moves.push((
+                first_hop + number_squares * number_squares * rank,
+                path.clone(),
+            ));
+        }
+        legal_moves
     }
 
-    fn legal_actions(&self) -> Vec<usize> {
-        let available_simple_moves = &self.game.get_available_simple_moves();
-        let available_jump_moves = &self.game.get_available_jump_moves();
-        if available_jump_moves.is_empty() {
-            available_simple_moves
+    /// The path of a legal action
+    fn action_to_positions(&self, action: usize) -> Option<Vec<BoardPosition>> {
+        self.legal_moves()
+            .into_iter()
+            .find(|(legal_action, _)| *legal_action == action)
+            .map(|(_, path)| path)
+    }
+
+    /// The action of a legal move, whichever of the paths taking the same
+    /// pieces to the same square is given
+    fn positions_to_action(&self, positions: &[BoardPosition]) -> Option<usize> {
+        let captured = sorted_captures(&self.game, positions);
+        self.legal_moves()
+            .into_iter()
+            .find(|(_, path)| {
+                path[0] == positions[0]
+                    && path.last() == positions.last()
+                    && sorted_captures(&self.game, path) == captured
+            })
+            .map(|(action, _)| action)
+    }
+
+    fn squares_to_action(&self, squares: &[usize]) -> Option<usize> {
+        let positions: Vec<BoardPosition> = squares
+            .iter()
+            .map(|&square| square_to_position(square, self.board_size()))
+            .collect();
+        self.positions_to_action(&positions)
+    }
+
+    // why an action that is not legal cannot be played, its first hop being
+    // tried on a copy of the game
+    fn action_error(&self, action: usize) -> MoveError {
+        let number_squares = self.number_squares();
+        let first_hop = action % (number_squares * number_squares);
+        let positions = vec![
+            square_to_position(first_hop / number_squares, self.board_size()),
+            square_to_position(first_hop % number_squares, self.board_size()),
+        ];
+        apply_positions_as_move(&mut self.game.clone(), positions)
+            .err()
+            .unwrap_or(MoveError::InvalidMove)
+    }
+
+    /// Plays an action. The winner gets a reward of 1 and the loser -1,
+    /// plus the change of their material difference scaled by
+    /// `material_reward` after every move.
+    fn step(&mut self, action: usize) -> Result<StepResult, MoveError> {
+        let positions = self
+            .action_to_positions(action)
+            .ok_or_else(|| self.action_error(action))?;
+        let material_before = self.material_difference();
+        apply_positions_as_move(&mut self.game, positions)?;
+
+        let shaped = self.material_reward * (self.material_difference() - material_before);
+        let mut rewards = [shaped, -shaped];
+        let termination = self.termination();
+        let winner = self.winner();
+        if let Some(winner) = winner {
+            rewards[winner as usize] += 1.0;
+            rewards[1 - winner as usize] -= 1.0;
+        }
+        Ok(StepResult {
+            observation: self.get_observation(),
+            rewards,
+            done: termination.is_some(),
+            winner,
+            termination,
+        })
+    }
+
+    /// The action chosen by an alpha-beta search of `depth` plies, stopped
+    /// early after `time_limit`. Only English draughts are supported.
+    fn best_action(
+        &self,
+        depth: u32,
+        time_limit: Option<Duration>,
+        weights: Weights,
+    ) -> Option<usize> {
+        let position = self.position();
+        let result = Searcher::new(weights).search(&position, depth, time_limit);
+        result.best_move.and_then(|the_move| {
+            let squares: Vec<usize> = the_move
+                .path
                 .iter()
-                .map(|positions| self.simple_move_to_action(positions))
-                .collect()
+                .map(|&square| square as usize)
+                .collect();
+            self.squares_to_action(&squares)
+        })
+    }
+
+    /// Number of move sequences of `depth` plies from the current position
+    fn perft(&self, depth: u32) -> u64 {
+        perft::perft(&mut self.game.clone(), depth)
+    }
+
+    fn is_english(&self) -> bool {
+        *self.game.rules() == Rules::english()
+    }
+
+    // the bitboard position, which only supports English draughts
+    fn position(&self) -> Position {
+        assert!(self.is_english(), "only English draughts are supported");
+        Position::from_board(self.game.board(), self.to_play() as usize)
+    }
+
+    fn set_position(&mut self, position: &Position) {
+        self.game.set_current_player(position.to_move());
+        self.game.set_board(position.to_board());
+        self.game.find_available_moves();
+        self.loaded_result = None;
+        self.start_fen = if *position == Position::new() {
+            None
         } else {
-            available_jump_moves
+            Some(pdn::to_fen(position))
+        };
+    }
+
+    fn get_fen(&self) -> String {
+        pdn::to_fen(&self.position())
+    }
+
+    fn set_fen(&mut self, fen: &str) -> Result<Vec<Vec<Vec<u8>>>, PdnError> {
+        let position = pdn::parse_fen(fen)?;
+        self.set_position(&position);
+        Ok(self.get_observation())
+    }
+
+    // PDN result of the game so far, from the point of view of player 1.
+    // A game going on keeps the result given in `tags`, or the one read by
+    // `load_pdn` while its moves are still those on the board.
+    fn pdn_result(&self, tags: &[(String, String)]) -> String {
+        let result = match (self.termination(), self.winner()) {
+            (None, _) => pdn::RESULT_UNKNOWN,
+            (Some(_), None) => pdn::RESULT_DRAW,
+            (Some(_), Some(0)) => pdn::RESULT_PLAYER_1_WINS,
+            (Some(_), Some(_)) => pdn::RESULT_PLAYER_2_WINS,
+        };
+        if result != pdn::RESULT_UNKNOWN {
+            return result.to_string();
+        }
+        if let Some((_, value)) = tags.iter().find(|(name, _)| name == "Result") {
+            return value.clone();
+        }
+        match &self.loaded_result {
+            Some((result, actions)) if *actions == self.history() => result.clone(),
+            _ => result.to_string(),
+        }
+    }
+
+    /// Writes the moves played since the start, or since the last
+    /// `set_state`, as a PDN game with the given tags. The `Result` tag is
+    /// kept for a game the board does not show as finished, while the `FEN`
+    /// and `SetUp` tags always describe the starting position of the game.
+    fn to_pdn(&self, mut tags: Vec<(String, String)>) -> String {
+        assert!(self.is_english(), "only English draughts are supported");
+        let result = self.pdn_result(&tags);
+        tags.retain(|(name, _)| name != "FEN" && name != "SetUp");
+        if let Some(fen) = &self.start_fen {
+            tags.push(("SetUp".to_string(), "1".to_string()));
+            tags.push(("FEN".to_string(), fen.clone()));
+        }
+        let game = PdnGame {
+            tags,
+            moves: self
+                .game
+                .history()
                 .iter()
-                .map(|positions| self.jump_move_to_action(positions))
-                .collect()
+                .map(|record| {
+                    record
+                        .path
+                        .iter()
+                        .map(|position| position_to_square(position, self.board_size()))
+                        .collect()
+                })
+                .collect(),
+            result,
+        };
+        pdn::write_game(&game).expect("the starting position is always valid")
+    }
+
+    /// Replays the first game of a PDN text from its starting position and
+    /// returns the actions played.
+    fn load_pdn(&mut self, text: &str) -> Result<Vec<usize>, PdnError> {
+        assert!(self.is_english(), "only English draughts are supported");
+        let game = match pdn::parse_games(text)?.into_iter().next() {
+            Some(game) => game,
+            None => return Ok(Vec::new()),
+        };
+        self.reset();
+        let actions = self.play_pdn_game(&game)?;
+        if game.result != pdn::RESULT_UNKNOWN {
+            self.loaded_result = Some((game.result, actions.clone()));
+        }
+        Ok(actions)
+    }
+
+    /// Plays the moves of a PDN game from its starting position and
+    /// returns their actions.
+    fn play_pdn_game(&mut self, game: &PdnGame) -> Result<Vec<usize>, PdnError> {
+        self.set_position(&game.start()?);
+        let mut actions = Vec::new();
+        for squares in game.moves.iter() {
+            let action = self
+                .squares_to_action(squares)
+                .expect("PDN moves are checked when read");
+            self.step(action).expect("PDN moves are checked when read");
+            actions.push(action);
         }
+        Ok(actions)
+    }
+
+    /// The actions played since the start, or since the last `set_state`
+    fn history(&self) -> Vec<usize> {
+        // each move is encoded in the position it was played from
+        let records = self.game.history().to_vec();
+        let mut replay = self.clone();
+        while replay.undo() {}
+        records
+            .iter()
+            .map(|record| {
+                let action = replay
+                    .positions_to_action(&record.path)
+                    .expect("the moves played were legal");
+                replay.redo();
+                action
+            })
+            .collect()
+    }
+
+    /// Takes back the last action, returns `false` if there is none
+    fn undo(&mut self) -> bool {
+        self.game.undo_move()
+    }
+
+    /// Plays again the last action taken back, returns `false` if there is
+    /// none
+    fn redo(&mut self) -> bool {
+        self.game.redo_move().is_some()
+    }
+
+    fn legal_actions(&self) -> Vec<usize> {
+        // capture paths taking the same pieces to the same square are one
+        // move, as they are for perft
+        self.legal_moves()
+            .into_iter()
+            .map(|(action, _)| action)
+            .collect()
     }
 
     fn to_play(&self) -> u8 {
@@ -166,7 +725,11 @@ impl Checkers {
     }
 
     fn reset(&mut self) -> Vec<Vec<Vec<u8>>> {
-        self.game = Game::new();
+        let draw_move_limit = self.game.draw_move_limit();
+        self.game = Game::with_rules(*self.game.rules());
+        self.game.set_draw_move_limit(draw_move_limit);
+        self.start_fen = None;
+        self.loaded_result = None;
         self.get_observation()
     }
 
@@ -176,6 +739,45 @@ impl Checkers {
     }
 
     fn get_observation(&self) -> Vec<Vec<Vec<u8>>> {
+        if self.perspective {
+            self.get_perspective_observation()
+        } else {
+            self.get_legacy_observation()
+        }
+    }
+
+    // Five 0/1 planes: own men, own kings, opponent men, opponent kings and
+    // a constant plane with the side to move. The player to move always
+    // sees their pieces at the bottom of the board.
+    fn get_perspective_observation(&self) -> Vec<Vec<Vec<u8>>> {
+        let me = self.game.current_player().id;
+        let board = self.game.get_board();
+        let n_rows = board.number_rows();
+        let n_columns = board.number_columns();
+        let mut planes = vec![vec![vec![0; n_columns]; n_rows]; 5];
+        for r in 0..n_rows {
+            for c in 0..n_columns {
+                let (row, column) = if self.is_flipped() {
+                    (r, n_columns - 1 - c)
+                } else {
+                    (n_rows - 1 - r, c)
+                };
+                if let Some(piece) = board.get_tile(r, c).get_piece() {
+                    let plane = match (piece.get_type(), piece.get_player_id() == me) {
+                        (PieceType::Man, true) => 0,
+                        (PieceType::King, true) => 1,
+                        (PieceType::Man, false) => 2,
+                        (PieceType::King, false) => 3,
+                    };
+                    planes[plane][row][column] = 1;
+                }
+                planes[4][row][column] = self.to_play();
+            }
+        }
+        planes
+    }
+
+    fn get_legacy_observation(&self) -> Vec<Vec<Vec<u8>>> {
         let values_0 = IntBoardValues {
             empty: 0,
             man_1: 10,
@@ -193,32 +795,91 @@ impl Checkers {
         vec![self.get_int_board(values_0), self.get_int_board(values_1)]
     }
 
-    fn set_state(&mut self, state: (u8, Vec<Vec<u8>>)) -> Vec<Vec<Vec<u8>>> {
+    /// Sets the player to move and the board, its rows given in board
+    /// order: player 1's back row first, unlike `get_state` which lists
+    /// them the other way round.
+    fn set_state(&mut self, state: (u8, Vec<Vec<u8>>)) -> Result<Vec<Vec<Vec<u8>>>, StateError> {
         let (current_player, board) = state;
-        let mut tiles = Vec::new();
-        for row in board.iter() {
-            for number_tile in row.iter() {
-                let tile: Box<dyn Tile> = match number_tile {
+        let board = self.board_from_state(current_player, &board)?;
+
+        self.game.set_current_player(current_player as usize);
+        self.game.set_board(board);
+        self.game.find_available_moves();
+        self.loaded_result = None;
+        self.start_fen = if self.is_english() {
+            Some(self.get_fen())
+        } else {
+            None
+        };
+        Ok(self.get_observation())
+    }
+
+    // Checks a state given to `set_state` against the rules: the board
+    // size, the piece codes, pieces on dark squares only, the number of
+    // pieces of each player and no man on the row where it is crowned.
+    fn board_from_state(&self, current_player: u8, board: &[Vec<u8>]) -> Result<Board, StateError> {
+        if current_player > 1 {
+            return Err(StateError::InvalidPlayer {
+                player: current_player,
+            });
+        }
+        let size = self.board_size();
+        let columns = board.first().map(|row| row.len()).unwrap_or(0);
+        if board.len() != size || board.iter().any(|row| row.len() != size) {
+            return Err(StateError::InvalidSize {
+                rows: board.len(),
+                columns,
+            });
+        }
+
+        let max_pieces = self.game.rules().rows_of_men * size / 2;
+        let mut counts = [0, 0];
+        let mut tiles: Vec<Box<dyn Tile>> = Vec::with_capacity(size * size);
+        for (r, row) in board.iter().enumerate() {
+            for (c, &code) in row.iter().enumerate() {
+                let tile: Box<dyn Tile> = match code {
                     0 => Box::new(EmptyTile),
                     1 => Box::new(OccupiedTile::new(Box::new(ManPiece { player_id: 1 }))),
                     2 => Box::new(OccupiedTile::new(Box::new(KingPiece { player_id: 1 }))),
                     3 => Box::new(OccupiedTile::new(Box::new(ManPiece { player_id: 2 }))),
                     4 => Box::new(OccupiedTile::new(Box::new(KingPiece { player_id: 2 }))),
-                    _ => unreachable!(),
+                    _ => {
+                        return Err(StateError::InvalidPiece {
+                            row: r,
+                            column: c,
+                            code,
+                        })
+                    }
                 };
-                tiles.push(tile)
+                if code != 0 {
+                    if (r + c) % 2 != 0 {
+                        return Err(StateError::LightSquare { row: r, column: c });
+                    }
+                    let crowning_row = match code {
+                        1 => Some(size - 1),
+                        3 => Some(0),
+                        _ => None,
+                    };
+                    if crowning_row == Some(r) {
+                        return Err(StateError::ManOnCrowningRow { row: r, column: c });
+                    }
+                    counts[(code as usize - 1) / 2] += 1;
+                }
+                tiles.push(tile);
+            }
+        }
+        for (player, &count) in counts.iter().enumerate() {
+            if count > max_pieces {
+                return Err(StateError::TooManyPieces {
+                    player: player as u8 + 1,
+                    count,
+                });
             }
         }
 
-        let n_rows = board.len();
-        let n_columns = board[0].len();
-        let mut board = Board::new(n_rows, n_columns);
-        board.set_tiles(tiles);
-
-        self.game.set_current_player(current_player as usize);
-        self.game.set_board(board);
-        self.game.find_available_moves();
-        self.get_observation()
+        let mut new_board = Board::new(size, size);
+        new_board.set_tiles(tiles);
+        Ok(new_board)
     }
 
     fn get_state(&self) -> (u8, Vec<Vec<u8>>, bool) {
@@ -232,7 +893,7 @@ impl Checkers {
         (
             self.to_play(),
             self.get_int_board(values),
-            self.game.is_game_over(),
+            self.termination().is_some(),
         )
     }
 
@@ -260,3 +921,465 @@ impl Checkers {
         observation
     }
 }
+
+impl MctsGame for Checkers {
+    type Observation = Vec<Vec<Vec<u8>>>;
+
+    fn legal_actions(&self) -> Vec<usize> {
+        Checkers::legal_actions(self)
+    }
+
+    fn apply_action(&mut self, action: usize) {
+        self.step(action).expect("only legal actions are searched");
+    }
+
+    fn to_play(&self) -> u8 {
+        Checkers::to_play(self)
+    }
+
+    fn is_terminal(&self) -> bool {
+        self.termination().is_some()
+    }
+
+    fn terminal_value(&self, player: u8) -> f32 {
+        match self.winner() {
+            None => 0.0,
+            Some(winner) if winner == player => 1.0,
+            Some(_) => -1.0,
+        }
+    }
+
+    fn observation(&self) -> Self::Observation {
+        self.get_observation()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn flip_action_is_an_involution() {
+        let checkers = Checkers::new(true, None, Rules::english());
+        for action in 0..3 * NUMBER_SQUARES * NUMBER_SQUARES {
+            assert_eq!(action, checkers.flip_action(checkers.flip_action(action)));
+        }
+    }
+
+    #[test]
+    fn perspective_observation_is_symmetric_at_start() {
+        let mut checkers = Checkers::new(true, None, Rules::english());
+        let first = checkers.get_observation();
+        assert_eq!(vec![vec![0; 8]; 8], first[4]);
+
+        // mirror the first move of player 1 so both sides see the same board
+        checkers.step(9 * 32 + 13).unwrap();
+        let mask = checkers.legal_action_mask();
+        assert!(mask[9 * 32 + 13]);
+
+        let second = checkers.get_observation();
+        assert_eq!(vec![vec![1; 8]; 8], second[4]);
+        // the men of player 2 are rotated to the bottom, where player 1 saw
+        // theirs
+        let rotate = |plane: &Vec<Vec<u8>>| -> Vec<Vec<u8>> {
+            plane
+                .iter()
+                .rev()
+                .map(|row| row.iter().rev().copied().collect())
+                .collect()
+        };
+        assert_eq!(first[0], second[0]);
+        assert_eq!(rotate(&first[0]), first[2]);
+        assert_ne!(first[0], rotate(&second[0]));
+        assert_eq!(second[2].concat().iter().sum::<u8>(), 12);
+        assert_ne!(first[2], second[2]);
+    }
+
+    #[test]
+    fn captures_sharing_a_first_hop_are_ranked() {
+        let mut checkers = Checkers::new(false, None, Rules::english());
+        // player 1 man on 9 jumps 13 to 18, then 21 to 25 or 22 to 27
+        let mut board = vec![vec![0; 8]; 8];
+        board[2][2] = 1;
+        board[3][3] = 3;
+        board[5][3] = 3;
+        board[5][5] = 3;
+        checkers.set_state((0, board.clone())).unwrap();
+
+        let first_hop = 9 * 32 + 18;
+        let mut actions = checkers.legal_actions();
+        actions.sort_unstable();
+        assert_eq!(vec![first_hop, first_hop + 1024], actions);
+        assert_eq!(Some(first_hop), checkers.squares_to_action(&[9, 18, 25]));
+        assert_eq!(
+            Some(first_hop + 1024),
+            checkers.squares_to_action(&[9, 18, 27])
+        );
+        let path = checkers.action_to_positions(first_hop + 1024).unwrap();
+        assert_eq!(BoardPosition::new(6, 6), path[2]);
+
+        // the same position with the colours swapped ranks them alike
+        let mut flipped = vec![vec![0; 8]; 8];
+        for (r, row) in board.iter().enumerate() {
+            for (c, &code) in row.iter().enumerate() {
+                flipped[7 - r][7 - c] = match code {
+                    0 => 0,
+                    code if code <= 2 => code + 2,
+                    code => code - 2,
+                };
+            }
+        }
+        let mut other = Checkers::new(false, None, Rules::english());
+        other.set_state((1, flipped)).unwrap();
+        let mut flipped_actions: Vec<usize> = other
+            .legal_actions()
+            .into_iter()
+            .map(|action| other.flip_action(action))
+            .collect();
+        flipped_actions.sort_unstable();
+        assert_eq!(actions, flipped_actions);
+        assert_eq!(
+            Some(other.flip_action(first_hop + 1024)),
+            other.squares_to_action(&[22, 13, 4])
+        );
+    }
+
+    #[test]
+    fn multi_jump_is_a_single_action() {
+        let mut checkers = Checkers::new(false, None, Rules::english());
+        // player 1 man on 9, player 2 men on 13 and 21, 18 and 25 empty
+        let mut board = vec![vec![0; 8]; 8];
+        board[2][2] = 1;
+        board[3][3] = 3;
+        board[5][3] = 3;
+        board[7][7] = 3;
+        checkers.set_state((0, board)).unwrap();
+
+        // alone to start with its hop, the double jump is that hop
+        let double_jump = 9 * 32 + 18;
+        assert_eq!(vec![double_jump], checkers.legal_actions());
+        assert_eq!(Some(double_jump), checkers.squares_to_action(&[9, 18, 25]));
+        // the mask keeps to the first hops
+        let mask = checkers.legal_action_mask();
+        assert_eq!(NUMBER_SQUARES * NUMBER_SQUARES, mask.len());
+        let first_hops: Vec<usize> = (0..mask.len()).filter(|&a| mask[a]).collect();
+        assert_eq!(vec![9 * 32 + 18], first_hops);
+
+        let result = checkers.step(double_jump).unwrap();
+        assert!(!result.done);
+        assert_eq!(1, checkers.to_play());
+        let (_, board, _) = checkers.get_state();
+        assert_eq!(0, board[7 - 3][3]);
+        assert_eq!(0, board[7 - 5][3]);
+        assert_eq!(1, board[7 - 6][2]);
+    }
+
+    #[test]
+    fn legal_action_mask_matches_legal_actions() {
+        let checkers = Checkers::new(false, None, Rules::english());
+        let mask = checkers.legal_action_mask();
+        assert_eq!(NUMBER_SQUARES * NUMBER_SQUARES, mask.len());
+        let mut actions: Vec<usize> = (0..mask.len()).filter(|&a| mask[a]).collect();
+        let mut legal_actions = checkers.legal_actions();
+        actions.sort_unstable();
+        legal_actions.sort_unstable();
+        assert_eq!(legal_actions, actions);
+    }
+    #[test]
+    fn international_actions_use_fifty_squares() {
+        let mut checkers = Checkers::new(true, None, Rules::international());
+        let mask = checkers.legal_action_mask();
+        assert_eq!(50 * 50, mask.len());
+        assert_eq!(9, mask.iter().filter(|&&legal| legal).count());
+        assert_eq!(50 * 50 - 1, checkers.flip_action(0));
+
+        // 15 -> 20 moves the man on the last row of player 1
+        let result = checkers.step(15 * 50 + 20).unwrap();
+        assert!(!result.done);
+        assert_eq!(10, result.observation[0].len());
+        assert_eq!(1, checkers.to_play());
+    }
+
+    #[test]
+    fn best_action_is_legal() {
+        let mut checkers = Checkers::new(false, None, Rules::english());
+        for _ in 0..6 {
+            let action = checkers.best_action(3, None, Weights::default()).unwrap();
+            assert!(checkers.legal_actions().contains(&action));
+            checkers.step(action).unwrap();
+        }
+    }
+
+    #[test]
+    fn pdn_round_trip() {
+        let mut checkers = Checkers::new(false, None, Rules::english());
+        let text = "1. 11-15 22-18 2. 15x22 25x18 *";
+        let actions = checkers.load_pdn(text).unwrap();
+        assert_eq!(4, actions.len());
+        assert_eq!(0, checkers.to_play());
+        let written = checkers.to_pdn(vec![("Event".to_string(), "Test".to_string())]);
+        assert!(written.starts_with("[Event \"Test\"]"));
+        assert!(written.contains(text));
+
+        let fen = checkers.get_fen();
+        let mut other = Checkers::new(false, None, Rules::english());
+        other.set_fen(&fen).unwrap();
+        assert_eq!(checkers.get_state(), other.get_state());
+        assert!(other
+            .to_pdn(Vec::new())
+            .contains(&format!("[FEN \"{}\"]", fen)));
+    }
+
+    #[test]
+    fn pdn_result_is_written_back() {
+        let mut checkers = Checkers::new(false, None, Rules::english());
+        let text = "[Result \"0-1\"]\n1. 11-15 22-18 0-1";
+        checkers.load_pdn(text).unwrap();
+        let written = checkers.to_pdn(Vec::new());
+        assert!(written.contains("[Result \"0-1\"]"));
+        assert!(written.ends_with("22-18 0-1\n"));
+
+        // the result no longer holds once the game goes on
+        checkers.undo();
+        assert!(checkers.to_pdn(Vec::new()).contains("[Result \"*\"]"));
+        let tags = vec![("Result".to_string(), "1-0".to_string())];
+        assert!(checkers.to_pdn(tags).contains("[Result \"1-0\"]"));
+    }
+
+    #[test]
+    fn pdn_start_comes_from_the_board() {
+        let mut checkers = Checkers::new(false, None, Rules::english());
+        checkers.step(checkers.legal_actions()[0]).unwrap();
+        let tags = vec![
+            ("SetUp".to_string(), "1".to_string()),
+            ("FEN".to_string(), "not a position".to_string()),
+        ];
+        let written = checkers.to_pdn(tags);
+        assert!(!written.contains("FEN"));
+        assert!(!written.contains("SetUp"));
+        assert!(written.contains("1. "));
+
+        checkers.set_fen("W:W30:B5").unwrap();
+        checkers.step(checkers.legal_actions()[0]).unwrap();
+        let tags = vec![("FEN".to_string(), "B:W21:B1".to_string())];
+        let written = checkers.to_pdn(tags);
+        assert!(written.contains("[FEN \"W:W30:B5\"]"));
+        assert!(!written.contains("B:W21:B1"));
+        assert!(written.contains("1... 30-"));
+    }
+
+    #[test]
+    fn duplicate_captures_are_one_action() {
+        let mut checkers = Checkers::new(false, None, Rules::english());
+        // the king can take the four men round the loop either way
+        checkers.set_fen("B:W6,7,14,15:BK11").unwrap();
+        let actions = checkers.legal_actions();
+        let paths = perft::legal_paths(&checkers.game);
+        assert_eq!(1, actions.len());
+        assert_eq!(
+            vec![checkers.positions_to_action(&paths[0]).unwrap()],
+            actions
+        );
+        assert_eq!(1, checkers.perft(1));
+    }
+
+    #[test]
+    fn undo_redo_history() {
+        let mut checkers = Checkers::new(false, None, Rules::english());
+        let start = checkers.get_state();
+        checkers.step(9 * 32 + 13).unwrap();
+        let reply = checkers.legal_actions()[0];
+        checkers.step(reply).unwrap();
+        assert_eq!(vec![9 * 32 + 13, reply], checkers.history());
+
+        assert!(checkers.undo());
+        assert!(checkers.undo());
+        assert!(!checkers.undo());
+        assert_eq!(start, checkers.get_state());
+        assert!(checkers.history().is_empty());
+
+        assert!(checkers.redo());
+        assert_eq!(vec![9 * 32 + 13], checkers.history());
+    }
+
+    #[test]
+    fn set_state_round_trip() {
+        let mut checkers = Checkers::new(false, None, Rules::english());
+        checkers.step(9 * 32 + 13).unwrap();
+        let (to_play, mut board, _) = checkers.get_state();
+        // get_state lists the rows from player 2's back row
+        board.reverse();
+        let mut other = Checkers::new(false, None, Rules::english());
+        other.set_state((to_play, board)).unwrap();
+        assert_eq!(checkers.get_state(), other.get_state());
+        assert_eq!(checkers.legal_actions(), other.legal_actions());
+    }
+
+    #[test]
+    fn canonical_state_swaps_colours_for_player_2() {
+        let mut checkers = Checkers::new(false, None, Rules::english());
+        let (state, flipped) = checkers.canonical_state();
+        assert!(!flipped);
+        assert_eq!(checkers.get_state().1, state.1);
+
+        checkers.step(9 * 32 + 13).unwrap();
+        let ((to_play, mut board), flipped) = checkers.canonical_state();
+        assert!(flipped);
+        board.reverse();
+        let mut canonical = Checkers::new(false, None, Rules::english());
+        canonical.set_state((to_play, board)).unwrap();
+        let mut actions: Vec<usize> = canonical
+            .legal_actions()
+            .into_iter()
+            .map(|action| canonical.flip_action(action))
+            .collect();
+        actions.sort_unstable();
+        let mut expected = checkers.legal_actions();
+        expected.sort_unstable();
+        assert_eq!(expected, actions);
+
+        // the colour flip of an observation and its mask is the canonical
+        // position's
+        let mask: Vec<f32> = checkers
+            .legal_action_mask()
+            .into_iter()
+            .map(|legal| legal as u8 as f32)
+            .collect();
+        let samples = checkers.symmetric_samples(&checkers.get_observation(), &mask);
+        assert_eq!(2, samples.len());
+        assert_eq!(canonical.get_observation(), samples[1].0);
+        let canonical_mask: Vec<f32> = canonical
+            .legal_action_mask()
+            .into_iter()
+            .map(|legal| legal as u8 as f32)
+            .collect();
+        assert_eq!(canonical_mask, samples[1].1);
+    }
+
+    #[test]
+    fn colour_flip_in_perspective_only_changes_the_side_to_move() {
+        let mut checkers = Checkers::new(true, None, Rules::english());
+        checkers.step(9 * 32 + 13).unwrap();
+        let ((to_play, mut board), _) = checkers.canonical_state();
+        board.reverse();
+        let mut canonical = Checkers::new(true, None, Rules::english());
+        canonical.set_state((to_play, board)).unwrap();
+        let policy = vec![0.5; NUMBER_SQUARES * NUMBER_SQUARES];
+        let (observation, flipped_policy) =
+            checkers.colour_flipped(&checkers.get_observation(), &policy);
+        assert_eq!(canonical.get_observation(), observation);
+        assert_eq!(policy, flipped_policy);
+        assert_eq!(canonical.legal_action_mask(), checkers.legal_action_mask());
+    }
+
+    #[test]
+    fn invalid_states_are_rejected() {
+        let mut checkers = Checkers::new(false, None, Rules::english());
+        let (_, mut start, _) = checkers.get_state();
+        start.reverse();
+        let with = |row: usize, column: usize, code: u8| {
+            let mut board = vec![vec![0; 8]; 8];
+            board[row][column] = code;
+            board
+        };
+
+        assert_eq!(
+            Err(StateError::InvalidPlayer { player: 2 }),
+            checkers.set_state((2, start.clone()))
+        );
+        assert_eq!(
+            Err(StateError::InvalidSize {
+                rows: 7,
+                columns: 8
+            }),
+            checkers.set_state((0, start[1..].to_vec()))
+        );
+        assert_eq!(
+            Err(StateError::InvalidPiece {
+                row: 7,
+                column: 0,
+                code: 5
+            }),
+            checkers.set_state((0, with(7, 0, 5)))
+        );
+        assert_eq!(
+            Err(StateError::LightSquare { row: 7, column: 0 }),
+            checkers.set_state((0, with(7, 0, 1)))
+        );
+        assert_eq!(
+            Err(StateError::ManOnCrowningRow { row: 7, column: 1 }),
+            checkers.set_state((0, with(7, 1, 1)))
+        );
+        assert!(checkers.set_state((0, with(7, 1, 2))).is_ok());
+        let mut crowded = start.clone();
+        crowded[4][0] = 3;
+        assert_eq!(
+            Err(StateError::TooManyPieces {
+                player: 2,
+                count: 13
+            }),
+            checkers.set_state((0, crowded))
+        );
+        assert_eq!(MoveError::InvalidMove, checkers.step(0).err().unwrap());
+    }
+
+    #[test]
+    fn rewards_winner_and_termination() {
+        let mut checkers = Checkers::new(false, None, Rules::english());
+        checkers.set_material_reward(0.1);
+        // player 1 man on 9 takes the last player 2 man on 13
+        let mut board = vec![vec![0; 8]; 8];
+        board[2][2] = 1;
+        board[3][3] = 3;
+        checkers.set_state((0, board)).unwrap();
+        let result = checkers.step(9 * 32 + 18).unwrap();
+        assert!(result.done);
+        assert_eq!(Some(0), result.winner);
+        assert_eq!(Some(Termination::NoPieces), result.termination);
+        assert_eq!([1.1, -1.1], result.rewards);
+
+        // player 2 man on 7 is blocked by the man on 3
+        let mut board = vec![vec![0; 8]; 8];
+        board[3][1] = 1;
+        board[1][7] = 3;
+        board[0][6] = 1;
+        checkers.set_state((0, board)).unwrap();
+        let result = checkers.step(12 * 32 + 16).unwrap();
+        assert!(result.done);
+        assert_eq!(Some(0), result.winner);
+        assert_eq!(Some(Termination::Blocked), result.termination);
+        assert_eq!([1.0, -1.0], result.rewards);
+    }
+
+    #[test]
+    fn endgame_database_adjudicates() {
+        let database = Arc::new(Database::build(2));
+        let mut checkers = Checkers::new(false, None, Rules::english());
+        // player 1 king on 9 takes the man on 13, leaving two kings
+        let mut board = vec![vec![0; 8]; 8];
+        board[2][2] = 2;
+        board[3][3] = 3;
+        board[7][7] = 4;
+        checkers.set_state((0, board.clone())).unwrap();
+        assert_eq!(None, checkers.probe_endgame());
+
+        checkers.set_endgame(Some(database.clone()), false);
+        let result = checkers.step(9 * 32 + 18).unwrap();
+        assert!(!result.done);
+        let value = checkers.probe_endgame().unwrap();
+        assert_eq!(database.probe(&checkers.position()), Some(value));
+
+        checkers.set_endgame(Some(database), true);
+        checkers.set_state((0, board)).unwrap();
+        let result = checkers.step(9 * 32 + 18).unwrap();
+        assert!(result.done);
+        assert_eq!(Some(Termination::Endgame(value)), result.termination);
+        let expected_winner = match value {
+            Value::Win(_) => Some(1),
+            Value::Loss(_) => Some(0),
+            Value::Draw => None,
+        };
+        assert_eq!(expected_winner, result.winner);
+        assert!(MctsGame::is_terminal(&checkers));
+    }
+}
@@ -1,28 +1,79 @@
-use super::Checkers;
+use super::checkers::endgame::{Database, Value};
+use super::checkers::pdn::{self, PdnError};
+use super::checkers::search::Weights;
+use super::{Checkers, MoveError, Rules};
+use crate::mcts::python::SearchResult;
+use crate::mcts::{self, Mcts, MctsConfig};
+use pyo3::create_exception;
+use pyo3::exceptions::{PyIOError, PyValueError};
 use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::sync::Arc;
+use std::time::Duration;
 
 // PYTHON MODULE
 // ---------------------------------------------------------
 // ---------------------------------------------------------
 #[pymodule]
-fn checkers(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
+fn checkers(py: Python<'_>, m: &PyModule) -> PyResult<()> {
     m.add_class::<CheckersEngine>()?;
+    m.add("IllegalMoveError", py.get_type::<IllegalMoveError>())?;
+    m.add("MustJumpError", py.get_type::<MustJumpError>())?;
+    m.add("InvalidStateError", py.get_type::<InvalidStateError>())?;
 
     Ok(())
 }
 
+create_exception!(zarena, IllegalMoveError, PyValueError);
+create_exception!(zarena, MustJumpError, IllegalMoveError);
+create_exception!(zarena, InvalidStateError, PyValueError);
+
+/// An observation and a policy over first hops
+type Sample = (Vec<Vec<Vec<u8>>>, Vec<f32>);
+
+/// Observation, reward of each player, done, winner and termination reason
+type StepOutput = (
+    Vec<Vec<Vec<u8>>>,
+    Vec<f32>,
+    bool,
+    Option<u8>,
+    Option<&'static str>,
+);
+
 #[pyclass]
 pub struct CheckersEngine {
     game: Checkers,
+    mcts: Mcts,
 }
 
 #[pymethods]
 impl CheckersEngine {
     #[new]
-    fn new() -> Self {
-        CheckersEngine {
-            game: Checkers::new(),
-        }
+    #[args(
+        perspective = false,
+        draw_move_limit = "None",
+        variant = "\"english\"",
+        material_reward = "0.0"
+    )]
+    fn new(
+        perspective: bool,
+        draw_move_limit: Option<usize>,
+        variant: &str,
+        material_reward: f32,
+    ) -> PyResult<Self> {
+        let rules = Rules::from_name(variant).ok_or_else(|| {
+            PyValueError::new_err(format!(
+                "unknown variant '{}', expected 'english', 'international', 'brazilian', \
+                 'russian' or 'italian'",
+                variant
+            ))
+        })?;
+        let mut game = Checkers::new(perspective, draw_move_limit, rules);
+        game.set_material_reward(material_reward);
+        Ok(CheckersEngine {
+            game,
+            mcts: Mcts::new(MctsConfig::default()),
+        })
     }
 
     pub fn legal_actions(&self) -> PyResult<Vec<usize>> {
@@ -30,9 +81,84 @@ impl CheckersEngine {
         Ok(legal_actions)
     }
 
-    pub fn step(&mut self, action: usize) -> PyResult<(Vec<Vec<Vec<u8>>>, f32, bool)> {
-        let (a, b, c) = self.game.step(action);
-        Ok((a, b, c))
+    /// A 1024 long mask indexed by `from * 32 + to` (`N * N` long for boards
+    /// with `N` playable squares) marking the first hop of the legal
+    /// actions, the full capture paths being given by `legal_actions`. With
+    /// `perspective` enabled it is given from the point of view of the
+    /// player to move, use `flip_action` to turn those indices back into
+    /// engine actions.
+    pub fn legal_action_mask(&self) -> PyResult<Vec<bool>> {
+        Ok(self.game.legal_action_mask())
+    }
+
+    /// Maps an action to the same move seen from the opposite side of the
+    /// board. Only needed with `perspective` when player 2 is to move.
+    pub fn flip_action(&self, action: usize) -> PyResult<usize> {
+        Ok(self.game.flip_action(action))
+    }
+
+    /// `[(observation, policy), ...]` for the given pair and its colour
+    /// flip, the same position with the colours swapped, with `policy`
+    /// laid out as `legal_action_mask`.
+    pub fn symmetries(
+        &self,
+        observation: Vec<Vec<Vec<u8>>>,
+        policy: Vec<f32>,
+    ) -> PyResult<Vec<Sample>> {
+        let size = self.game.board_size();
+        let planes = if self.game.perspective { 5 } else { 2 };
+        if observation.len() != planes
+            || observation
+                .iter()
+                .any(|plane| plane.len() != size || plane.iter().any(|row| row.len() != size))
+        {
+            return Err(PyValueError::new_err(format!(
+                "observation has to be {} planes of {}x{}",
+                planes, size, size
+            )));
+        }
+        let first_hops = self.game.number_squares() * self.game.number_squares();
+        if policy.len() != first_hops {
+            return Err(PyValueError::new_err(format!(
+                "policy has {} entries, {} expected",
+                policy.len(),
+                first_hops
+            )));
+        }
+        Ok(self.game.symmetric_samples(&observation, &policy))
+    }
+
+    /// `((to_play, board_int), flipped)`: the state laid out as in
+    /// `get_state` with player 1 to move, the colours being swapped when
+    /// player 2 is to move. Actions of a flipped state map back with
+    /// `flip_action`.
+    pub fn canonical_state(&self) -> PyResult<((u8, Vec<Vec<u8>>), bool)> {
+        Ok(self.game.canonical_state())
+    }
+
+    /// Returns the observation, the rewards of both players indexed by
+    /// `to_play`, whether the game is over, the winner and the reason the
+    /// game ended ("no_pieces", "blocked" or "draw").
+    /// Raises `MustJumpError` when a capture was available and
+    /// `IllegalMoveError` for any other action that is not legal.
+    pub fn step(&mut self, action: usize) -> PyResult<StepOutput> {
+        let result = self.game.step(action).map_err(|error| match error {
+            MoveError::InvalidMove => {
+                IllegalMoveError::new_err(format!("illegal action {}", action))
+            }
+            MoveError::ShouldHaveJumped => MustJumpError::new_err(format!(
+                "illegal action {}, a capture has to be played",
+                action
+            )),
+        })?;
+        self.mcts.advance(action);
+        Ok((
+            result.observation,
+            result.rewards.to_vec(),
+            result.done,
+            result.winner,
+            result.termination.map(|termination| termination.name()),
+        ))
     }
 
     pub fn get_state(&self) -> PyResult<(u8, Vec<Vec<u8>>, bool)> {
@@ -40,8 +166,15 @@ impl CheckersEngine {
         Ok((to_play, board_int, done))
     }
 
+    /// Takes the board rows from player 1's back row, the reverse of the
+    /// rows of `get_state`, and raises `InvalidStateError` for a state that
+    /// breaks the rules.
     pub fn set_state(&mut self, state: (u8, Vec<Vec<u8>>)) -> PyResult<Vec<Vec<Vec<u8>>>> {
-        let observation = self.game.set_state(state);
+        let observation = self
+            .game
+            .set_state(state)
+            .map_err(|error| InvalidStateError::new_err(error.to_string()))?;
+        self.mcts.clear();
         Ok(observation)
     }
 
@@ -51,10 +184,219 @@ impl CheckersEngine {
 
     pub fn reset(&mut self) -> PyResult<Vec<Vec<Vec<u8>>>> {
         let observation = self.game.reset();
+        self.mcts.clear();
+        Ok(observation)
+    }
+
+    /// Replaces the search configuration and drops the current tree.
+    /// See `mcts::python::config_from_dict` for the accepted keys.
+    pub fn set_mcts_config(&mut self, config: &PyDict) -> PyResult<()> {
+        self.mcts = Mcts::new(mcts::python::config_from_dict(config)?);
+        Ok(())
+    }
+
+    /// Searches the current position and returns
+    /// `(best_action, [(action, visits), ...], root_value)`.
+    #[args(evaluator = "None", rollouts = 1)]
+    pub fn mcts_search(
+        &mut self,
+        py: Python,
+        evaluator: Option<PyObject>,
+        rollouts: usize,
+    ) -> PyResult<SearchResult> {
+        mcts::python::search(py, &mut self.mcts, &self.game, evaluator, rollouts)
+    }
+
+    /// The action chosen by an alpha-beta search of `depth` plies, or
+    /// `None` when there is no legal action. The search stops early after
+    /// `time_limit` seconds, a negative or infinite `time_limit` raising a
+    /// `ValueError`. `weights` overrides the evaluation weights,
+    /// e.g. `{"man": 100, "king": 160, "back_rank": 10, "mobility": 2}`.
+    #[args(depth = 6, time_limit = "None", weights = "None")]
+    pub fn best_action(
+        &self,
+        depth: u32,
+        time_limit: Option<f64>,
+        weights: Option<&PyDict>,
+    ) -> PyResult<Option<usize>> {
+        self.require_english("best_action")?;
+        let weights = match weights {
+            Some(dict) => weights_from_dict(dict)?,
+            None => Weights::default(),
+        };
+        let time_limit = match time_limit {
+            // Duration panics on negative, infinite and NaN seconds
+            Some(seconds) if !(seconds >= 0.0 && seconds < u64::MAX as f64) => {
+                return Err(PyValueError::new_err(format!(
+                    "time_limit must be a finite, non-negative number of seconds, got {}",
+                    seconds
+                )));
+            }
+            time_limit => time_limit.map(Duration::from_secs_f64),
+        };
+        Ok(self.game.best_action(depth, time_limit, weights))
+    }
+
+    /// Counts the move sequences of `depth` plies from the current position,
+    /// to check move generation against published counts
+    pub fn perft(&self, depth: u32) -> PyResult<u64> {
+        Ok(self.game.perft(depth))
+    }
+
+    /// The actions played since the start, or since the last `set_state`
+    pub fn history(&self) -> PyResult<Vec<usize>> {
+        Ok(self.game.history())
+    }
+
+    /// Takes back the last action and returns the new observation, or
+    /// `None` when there is no action to take back.
+    pub fn undo(&mut self) -> PyResult<Option<Vec<Vec<Vec<u8>>>>> {
+        if !self.game.undo() {
+            return Ok(None);
+        }
+        self.mcts.clear();
+        Ok(Some(self.game.get_observation()))
+    }
+
+    /// Plays again the last action taken back by `undo` and returns the new
+    /// observation, or `None` when there is no action to play again.
+    pub fn redo(&mut self) -> PyResult<Option<Vec<Vec<Vec<u8>>>>> {
+        if !self.game.redo() {
+            return Ok(None);
+        }
+        self.mcts.clear();
+        Ok(Some(self.game.get_observation()))
+    }
+
+    /// The FEN tag value of the current position, e.g. `B:W21,22:B1,2`
+    pub fn get_fen(&self) -> PyResult<String> {
+        self.require_english("get_fen")?;
+        Ok(self.game.get_fen())
+    }
+
+    pub fn set_fen(&mut self, fen: &str) -> PyResult<Vec<Vec<Vec<u8>>>> {
+        self.require_english("set_fen")?;
+        let observation = self.game.set_fen(fen).map_err(pdn_error)?;
+        self.mcts.clear();
         Ok(observation)
     }
 
+    /// The game played so far in PDN, `tags` being a list of
+    /// `(name, value)` pairs such as `[("Event", "Training")]`. The result
+    /// of a game going on is the one of the `Result` tag, or the one read
+    /// by `load_pdn` while no other move was played. `FEN` and `SetUp` tags
+    /// are replaced by the starting position of the game.
+    #[args(tags = "Vec::new()")]
+    pub fn to_pdn(&self, tags: Vec<(String, String)>) -> PyResult<String> {
+        self.require_english("to_pdn")?;
+        Ok(self.game.to_pdn(tags))
+    }
+
+    /// Replays the first game of a PDN text and returns its actions.
+    pub fn load_pdn(&mut self, text: &str) -> PyResult<Vec<usize>> {
+        self.require_english("load_pdn")?;
+        let actions = self.game.load_pdn(text).map_err(pdn_error)?;
+        self.mcts.clear();
+        Ok(actions)
+    }
+
+    /// Reads every game of a PDN text as `(tags, actions, result)`, the
+    /// actions starting from the position of the `FEN` tag if there is one.
+    #[staticmethod]
+    pub fn read_pdn(text: &str) -> PyResult<Vec<(Vec<(String, String)>, Vec<usize>, String)>> {
+        let games = pdn::parse_games(text).map_err(pdn_error)?;
+        // actions are encoded in the position they are played from
+        let mut replay = Checkers::new(false, None, Rules::english());
+        games
+            .into_iter()
+            .map(|game| {
+                let actions = replay.play_pdn_game(&game).map_err(pdn_error)?;
+                Ok((game.tags, actions, game.result))
+            })
+            .collect()
+    }
+
+    /// Solves every english position with up to `max_pieces` pieces,
+    /// writes the database to `path` and returns the number of won or lost
+    /// positions. Three pieces take seconds, four take minutes and several
+    /// gigabytes of memory.
+    #[staticmethod]
+    pub fn build_endgame(py: Python, max_pieces: u32, path: &str) -> PyResult<usize> {
+        if !(1..=MAX_ENDGAME_PIECES).contains(&max_pieces) {
+            return Err(PyValueError::new_err(format!(
+                "max_pieces must be between 1 and {}",
+                MAX_ENDGAME_PIECES
+            )));
+        }
+        let database = py.allow_threads(|| Database::build(max_pieces));
+        database
+            .save(path)
+            .map_err(|error| PyIOError::new_err(error.to_string()))?;
+        Ok(database.len())
+    }
+
+    /// Loads a database written by `build_endgame`. With `adjudicate` the
+    /// game ends as soon as a position in the database is reached, won by
+    /// the side the database says wins.
+    #[args(adjudicate = true)]
+    pub fn load_endgame(&mut self, path: &str, adjudicate: bool) -> PyResult<()> {
+        self.require_english("load_endgame")?;
+        let database =
+            Database::load(path).map_err(|error| PyIOError::new_err(error.to_string()))?;
+        self.game.set_endgame(Some(Arc::new(database)), adjudicate);
+        self.mcts.clear();
+        Ok(())
+    }
+
+    /// The result of the current position with best play, for the player
+    /// to move, as `("win" | "loss" | "draw", plies to the end)`. `None`
+    /// when no database is loaded or there are too many pieces left.
+    pub fn probe_endgame(&self) -> PyResult<Option<(&'static str, u16)>> {
+        Ok(self.game.probe_endgame().map(|value| match value {
+            Value::Win(plies) => ("win", plies),
+            Value::Loss(plies) => ("loss", plies),
+            Value::Draw => ("draw", 0),
+        }))
+    }
+
     pub fn print(&self) {
         self.game.print();
     }
 }
+
+/// Largest endgame database `build_endgame` accepts
+const MAX_ENDGAME_PIECES: u32 = 4;
+
+impl CheckersEngine {
+    fn require_english(&self, method: &str) -> PyResult<()> {
+        if self.game.is_english() {
+            Ok(())
+        } else {
+            Err(PyValueError::new_err(format!(
+                "{} only supports the english variant",
+                method
+            )))
+        }
+    }
+}
+
+fn pdn_error(error: PdnError) -> PyErr {
+    PyValueError::new_err(error.to_string())
+}
+
+fn weights_from_dict(dict: &PyDict) -> PyResult<Weights> {
+    let mut weights = Weights::default();
+    if let Some(value) = dict.get_item("man") {
+        weights.man = value.extract()?;
+    }
+    if let Some(value) = dict.get_item("king") {
+        weights.king = value.extract()?;
+    }
+    if let Some(value) = dict.get_item("back_rank") {
+        weights.back_rank = value.extract()?;
+    }
+    if let Some(value) = dict.get_item("mobility") {
+        weights.mobility = value.extract()?;
+    }
+    Ok(weights)
+}
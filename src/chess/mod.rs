@@ -1503,3 +1503,62 @@ fn update_state(state: &mut State) {
     let squares_under_attack_by_white = get_squares_under_attack_by_player(state, Color::White);
     state.update_player_king_checked(Color::Black, &squares_under_attack_by_white);
 }
+
+// COLOUR FLIP
+// ---------------------------------------------------------
+// ---------------------------------------------------------
+// Castling and pawns tie the board to its orientation, so chess has no
+// symmetry of the board alone. Swapping the colour of every piece while
+// mirroring the ranks gives an equivalent position with the other player
+// to move, used to augment training data and to look positions up from
+// the point of view of white.
+
+/// Number of actions of the gym environment: `from * 64 + to` for moves,
+/// squares being `row * 8 + column` with row 0 the eighth rank, then the
+/// four castles and resigning
+pub const NUMBER_ACTIONS: usize = 64 * 64 + 5;
+
+/// `board` with the ranks mirrored and every piece given to the other
+/// player
+pub fn mirror_board(board: &Board) -> Board {
+    let mut mirrored = [[EMPTY_SQUARE_ID; 8]; 8];
+    for (row, squares) in board.iter().enumerate() {
+        for (col, piece_id) in squares.iter().enumerate() {
+            mirrored[7 - row][col] = -piece_id;
+        }
+    }
+    mirrored
+}
+
+/// `state` with the colours swapped: the board mirrored as in
+/// `mirror_board`, castling rights and checks handed to the other player
+/// and the other player to move
+pub fn mirror_state(state: &State) -> State {
+    State {
+        board: mirror_board(&state.board),
+        current_player: get_other_player(state.current_player),
+        white_king_on_board: state.black_king_on_board,
+        black_king_on_board: state.white_king_on_board,
+        white_king_castle_is_possible: state.black_king_castle_is_possible,
+        white_queen_castle_is_possible: state.black_queen_castle_is_possible,
+        black_king_castle_is_possible: state.white_king_castle_is_possible,
+        black_queen_castle_is_possible: state.white_queen_castle_is_possible,
+        white_king_is_checked: state.black_king_is_checked,
+        black_king_is_checked: state.white_king_is_checked,
+    }
+}
+
+/// The action of the gym environment playing the same move on the board
+/// of `mirror_state`. Applying it twice gives back `action`.
+pub fn mirror_action(action: usize) -> usize {
+    let mirror_square = |square: usize| (7 - square / 8) * 8 + square % 8;
+    match action {
+        // castles swap colour on the same side, resigning stays
+        4096 => 4098,
+        4097 => 4099,
+        4098 => 4096,
+        4099 => 4097,
+        4100 => 4100,
+        _ => mirror_square(action / 64) * 64 + mirror_square(action % 64),
+    }
+}
@@ -1,13 +1,17 @@
-use pyo3::exceptions::PyException;
+use pyo3::exceptions::{PyException, PyValueError};
 use pyo3::prelude::*;
 use pyo3::types::PyDict;
 
 use super::{
     convert_castle_move_to_string, convert_move_to_string, convert_move_to_type,
-    get_all_possible_moves, get_possible_castle_moves, is_game_over, next_state,
-    player_string_to_enum, update_state, Board, Castle, Color, Move, State,
+    get_all_possible_moves, get_possible_castle_moves, is_game_over, mirror_action, mirror_board,
+    mirror_state, next_state, player_string_to_enum, update_state, Board, Castle, Color, Move,
+    State, NUMBER_ACTIONS,
 };
 
+/// An observation and a policy over the actions
+type Sample = (Vec<Board>, Vec<f32>);
+
 // PYTHON MODULE
 // ---------------------------------------------------------
 // ---------------------------------------------------------
@@ -131,6 +135,62 @@ impl ChessEngine {
         let res = is_game_over(&states, &state, player);
         return Ok(res);
     }
+
+    /// The same position with the colours swapped: ranks mirrored, every
+    /// piece given to the other player along with its castling rights, and
+    /// the other player to move.
+    fn mirror_state<'a>(&mut self, _py: Python<'a>, state_py: &'a PyDict) -> PyResult<&'a PyDict> {
+        let state: State = convert_py_state(_py, state_py)?;
+        let mirrored_py = PyDict::new(_py);
+        mirror_state(&state).to_py_object(mirrored_py);
+        return Ok(mirrored_py);
+    }
+
+    /// `(state, flipped)`: the state with white to move, the colours being
+    /// swapped as in `mirror_state` when black is to move. Actions of a
+    /// flipped state map back with `mirror_action`.
+    fn canonical_state<'a>(
+        &mut self,
+        _py: Python<'a>,
+        state_py: &'a PyDict,
+    ) -> PyResult<(&'a PyDict, bool)> {
+        let state: State = convert_py_state(_py, state_py)?;
+        let flipped = state.current_player == Color::Black;
+        let canonical = if flipped { mirror_state(&state) } else { state };
+        let canonical_py = PyDict::new(_py);
+        canonical.to_py_object(canonical_py);
+        return Ok((canonical_py, flipped));
+    }
+
+    /// The action playing the same move on the board of `mirror_state`.
+    fn mirror_action(&self, action: usize) -> PyResult<usize> {
+        if action >= NUMBER_ACTIONS {
+            return Err(PyValueError::new_err(format!("invalid action {}", action)));
+        }
+        Ok(mirror_action(action))
+    }
+
+    /// `[(observation, policy), ...]` for the given pair and its colour
+    /// flip, with the observation a list of boards and the policy one entry
+    /// per action of the environment.
+    fn symmetries(&self, observation: Vec<Board>, policy: Vec<f32>) -> PyResult<Vec<Sample>> {
+        if policy.len() != NUMBER_ACTIONS {
+            return Err(PyValueError::new_err(format!(
+                "policy has {} entries, {} expected",
+                policy.len(),
+                NUMBER_ACTIONS
+            )));
+        }
+        let mirrored_observation = observation.iter().map(mirror_board).collect();
+        let mut mirrored_policy = vec![0.0; NUMBER_ACTIONS];
+        for (action, probability) in policy.iter().enumerate() {
+            mirrored_policy[mirror_action(action)] = *probability;
+        }
+        Ok(vec![
+            (observation, policy),
+            (mirrored_observation, mirrored_policy),
+        ])
+    }
 }
 
 fn convert_py_state<'a>(_py: Python<'a>, state_py: &'a PyDict) -> PyResult<State> {
@@ -1,9 +1,16 @@
 #[cfg(feature = "python")]
 pub mod python;
 
+use crate::mcts::MctsGame;
+use crate::symmetry::Symmetry;
 use rand::Rng;
 use std::fmt;
 
+mod solver;
+mod ultimate;
+pub use solver::{Solver, MAX_EMPTY_CELLS};
+pub use ultimate::{Ultimate, ULTIMATE_SIZE};
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 #[allow(dead_code)]
 pub enum TickType {
@@ -22,7 +29,7 @@ impl fmt::Display for TickType {
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[allow(dead_code)]
 pub enum Player {
     Noughts,
@@ -46,28 +53,121 @@ impl Player {
     }
 }
 
-#[derive(Debug)]
+/// Reasons for `step` to refuse an action
+#[derive(Debug, PartialEq, Eq)]
+pub enum ActionError {
+    /// The action is not a cell of the board
+    OutOfBoard {
+        action: usize,
+        cells: usize,
+    },
+    Occupied {
+        action: usize,
+    },
+    GameOver,
+    /// Ultimate tic-tac-toe: the action is not in the board the player was
+    /// sent to
+    WrongBoard {
+        action: usize,
+        board: usize,
+    },
+    /// Ultimate tic-tac-toe: the action is in a board already won or full
+    ClosedBoard {
+        action: usize,
+        board: usize,
+    },
+}
+
+impl fmt::Display for ActionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ActionError::OutOfBoard { action, cells } => write!(
+                f,
+                "invalid action {}, the board has {} cells",
+                action, cells
+            ),
+            ActionError::Occupied { action } => {
+                write!(f, "invalid action {}, the cell is already marked", action)
+            }
+            ActionError::GameOver => write!(f, "the game is over, reset it to play again"),
+            ActionError::WrongBoard { action, board } => write!(
+                f,
+                "invalid action {}, the player was sent to board {}",
+                action, board
+            ),
+            ActionError::ClosedBoard { action, board } => write!(
+                f,
+                "invalid action {}, board {} is already won or full",
+                action, board
+            ),
+        }
+    }
+}
+
+/// Observation, rewards of both players indexed by `to_play` and done
+pub type StepOutcome = (Vec<Vec<Vec<usize>>>, [f32; 2], bool);
+
+/// The four line directions: along a row, along a column and the two
+/// diagonals
+const DIRECTIONS: [(isize, isize); 4] = [(0, 1), (1, 0), (1, 1), (1, -1)];
+
+/// An m,n,k-game: players take turns marking the cells of a board with
+/// `rows` rows and `cols` columns, and the first to get `k` marks in a row,
+/// column or diagonal wins. Tic-tac-toe is the 3,3,3-game and Gomoku the
+/// 15,15,5-game.
+#[derive(Debug, Clone)]
 pub struct Tictactoe {
-    pub turn: u8,
+    pub rows: usize,
+    pub cols: usize,
+    pub k: usize,
+    pub turn: usize,
+    /// The player making the first mark after `reset`, Crosses by default
+    pub first_player: Player,
     pub current_player: Player,
-    pub board: [[TickType; 3]; 3],
+    pub board: Vec<Vec<TickType>>,
     pub winner: Option<Player>,
     pub done: bool,
+    /// The actions played since the last `reset` or `set_state`, for `undo`
+    pub history: Vec<usize>,
+    /// Reward of a player making an illegal action, which ends the game.
+    /// `None` to refuse illegal actions with an error instead.
+    pub illegal_action_penalty: Option<f32>,
+    /// The player who ended the game with an illegal action
+    pub forfeited_by: Option<Player>,
 }
 
 impl Tictactoe {
     #[allow(dead_code)]
-    pub fn new() -> Tictactoe {
+    pub fn new(rows: usize, cols: usize, k: usize) -> Tictactoe {
         Tictactoe {
+            rows,
+            cols,
+            k,
             turn: 0,
-            current_player: Player::Noughts,
-            board: [[TickType::Nil; 3]; 3],
+            first_player: Player::Crosses,
+            current_player: Player::Crosses,
+            board: vec![vec![TickType::Nil; cols]; rows],
             winner: None,
             done: false,
+            history: Vec::new(),
+            illegal_action_penalty: None,
+            forfeited_by: None,
         }
     }
+
+    /// Sets the player making the first mark, taking effect at the next
+    /// `reset`
+    pub fn set_first_player(&mut self, player: Player) {
+        self.first_player = player;
+    }
+
+    /// Makes an illegal action end the game with `penalty` as the reward
+    /// of the player who made it, or be refused with an error for `None`
+    pub fn set_illegal_action_penalty(&mut self, penalty: Option<f32>) {
+        self.illegal_action_penalty = penalty;
+    }
     #[allow(dead_code)]
-    pub fn get_state(&self) -> (u8, u8, Vec<Vec<isize>>, u8, bool) {
+    pub fn get_state(&self) -> (usize, u8, Vec<Vec<isize>>, u8, bool) {
         (
             self.turn,
             self.to_play(),
@@ -78,7 +178,7 @@ impl Tictactoe {
     }
     #[allow(dead_code)]
     pub fn set_state(&mut self, state: (u8, Vec<Vec<isize>>)) -> Vec<Vec<Vec<usize>>> {
-        let mut turn: u8 = 0;
+        let mut turn = 0;
         for row in state.1.iter() {
             for item in row {
                 if *item != 0 {
@@ -89,8 +189,10 @@ impl Tictactoe {
         self.turn = turn;
         self.set_to_play(state.0);
         self.set_board_int(state.1);
-        self.set_winner_int(2);
-        self.done = false;
+        self.winner = self.line_owner();
+        self.done = self.winner.is_some() || self.turn == self.rows * self.cols;
+        self.history.clear();
+        self.forfeited_by = None;
         self.get_observation()
     }
     #[allow(dead_code)]
@@ -102,9 +204,9 @@ impl Tictactoe {
     }
 
     fn set_board_int(&mut self, board_int: Vec<Vec<isize>>) {
-        let mut board: [[TickType; 3]; 3] = [[TickType::Nil; 3]; 3];
-        for (i, row) in board_int.iter().enumerate() {
-            for (j, item) in row.iter().enumerate() {
+        let mut board = vec![vec![TickType::Nil; self.cols]; self.rows];
+        for (i, row) in board_int.iter().enumerate().take(self.rows) {
+            for (j, item) in row.iter().enumerate().take(self.cols) {
                 match item {
                     1 => board[i][j] = TickType::Nought,
                     -1 => board[i][j] = TickType::Cross,
@@ -115,24 +217,12 @@ impl Tictactoe {
         self.board = board;
     }
 
-    fn set_winner_int(&mut self, player: u8) {
-        self.winner = match player {
-            0 => Some(Player::Crosses),
-            1 => Some(Player::Noughts),
-            _ => None,
-        }
-    }
-
     fn get_winner_int(&self) -> u8 {
-        match self.winner {
-            Some(Player::Crosses) => 0,
-            Some(Player::Noughts) => 1,
-            None => 2,
-        }
+        self.winner().unwrap_or(2)
     }
 
     fn get_board_int(&self) -> Vec<Vec<isize>> {
-        let mut board: Vec<Vec<isize>> = vec![vec![0; 3]; 3];
+        let mut board: Vec<Vec<isize>> = vec![vec![0; self.cols]; self.rows];
         for (i, row) in self.board.iter().enumerate() {
             for (j, item) in row.iter().enumerate() {
                 match item {
@@ -154,34 +244,106 @@ impl Tictactoe {
     #[allow(dead_code)]
     pub fn reset(&mut self) -> Vec<Vec<Vec<usize>>> {
         self.turn = 0;
-        self.current_player = Player::Crosses;
-        self.board = [[TickType::Nil; 3]; 3];
+        self.current_player = self.first_player;
+        self.board = vec![vec![TickType::Nil; self.cols]; self.rows];
         self.winner = None;
+        self.done = false;
+        self.history.clear();
+        self.forfeited_by = None;
         self.get_observation()
     }
     #[allow(dead_code)]
-    pub fn step(&mut self, action: usize) -> (Vec<Vec<Vec<usize>>>, f32, bool) {
-        let row = action / 3;
-        let col = action % 3;
+    /// Marks the cell `action` for the player to move and returns the
+    /// observation, the rewards of both players indexed by `to_play` and
+    /// whether the game is over. An action outside the board or on a
+    /// marked cell is refused, or ends the game when a penalty is set.
+    pub fn step(&mut self, action: usize) -> Result<StepOutcome, ActionError> {
+        if self.done {
+            return Err(ActionError::GameOver);
+        }
+        if action >= self.rows * self.cols {
+            return self.illegal_action(ActionError::OutOfBoard {
+                action,
+                cells: self.rows * self.cols,
+            });
+        }
+        let row = action / self.cols;
+        let col = action % self.cols;
 
-        self.place_mark(row, col);
-        if self.win_condition() {
-            self.winner = Some(self.current_player);
+        if let Err(error) = self.place_mark(row, col) {
+            return self.illegal_action(error);
+        }
+        self.history.push(action);
+        if self.completes_line(row, col, self.board[row][col]) {
+            // place_mark already handed the turn to the other player
+            self.winner = Some(self.current_player.other());
         }
-        self.done = self.winner.is_some() || self.legal_actions().len() == 0;
-        let reward = self.get_reward();
-        (self.get_observation(), reward, self.done)
+        self.done = self.winner.is_some() || self.turn == self.rows * self.cols;
+        Ok((self.get_observation(), self.get_rewards(), self.done))
     }
-    #[allow(dead_code)]
-    fn get_reward(&self) -> f32 {
-        if self.done {
-            if self.winner.is_some() {
-                return 1.0;
-            } else {
-                return 0.5;
+
+    // without a penalty `error` is handed back, with one the player to move
+    // forfeits the game
+    fn illegal_action(&mut self, error: ActionError) -> Result<StepOutcome, ActionError> {
+        if self.illegal_action_penalty.is_none() {
+            return Err(error);
+        }
+        self.forfeited_by = Some(self.current_player);
+        self.done = true;
+        Ok((self.get_observation(), self.get_rewards(), true))
+    }
+
+    /// Rewards of both players indexed by `to_play`: 1 for the winner and 0
+    /// for the loser, 0.5 each for a draw and 0 while the game goes on. A
+    /// player who made an illegal action gets the penalty and the other 0.
+    pub fn get_rewards(&self) -> [f32; 2] {
+        if let Some(player) = self.forfeited_by {
+            let mut rewards = [0.0; 2];
+            let index = match player {
+                Player::Crosses => 0,
+                Player::Noughts => 1,
+            };
+            rewards[index] = self.illegal_action_penalty.unwrap_or(0.0);
+            return rewards;
+        }
+        match self.winner() {
+            Some(winner) => {
+                let mut rewards = [0.0; 2];
+                rewards[winner as usize] = 1.0;
+                rewards
             }
+            None if self.done => [0.5; 2],
+            None => [0.0; 2],
         }
-        0.0
+    }
+
+    /// `to_play` index of the winner, `None` for a draw or a game in
+    /// progress
+    pub fn winner(&self) -> Option<u8> {
+        self.winner.map(|winner| match winner {
+            Player::Crosses => 0,
+            Player::Noughts => 1,
+        })
+    }
+
+    /// Takes back the last action, returns `false` if there is none
+    pub fn undo(&mut self) -> bool {
+        // an illegal action left the board as it was
+        if self.forfeited_by.take().is_some() {
+            self.done = false;
+            return true;
+        }
+        let action = match self.history.pop() {
+            Some(action) => action,
+            None => return false,
+        };
+        self.board[action / self.cols][action % self.cols] = TickType::Nil;
+        self.current_player = self.current_player.other();
+        self.turn -= 1;
+        // the game went on after every mark but the last one
+        self.winner = None;
+        self.done = false;
+        true
     }
 
     pub fn legal_actions(&self) -> Vec<usize> {
@@ -189,7 +351,7 @@ impl Tictactoe {
         for (i, row) in self.board.iter().enumerate() {
             for (j, item) in row.iter().enumerate() {
                 if *item == TickType::Nil {
-                    legal_actions.push(i * 3 + j);
+                    legal_actions.push(i * self.cols + j);
                 }
             }
         }
@@ -197,8 +359,8 @@ impl Tictactoe {
     }
 
     fn get_observation(&self) -> Vec<Vec<Vec<usize>>> {
-        let mut board_player_1 = vec![vec![0 as usize; 3]; 3];
-        let mut board_player_2 = vec![vec![0 as usize; 3]; 3];
+        let mut board_player_1 = vec![vec![0 as usize; self.cols]; self.rows];
+        let mut board_player_2 = vec![vec![0 as usize; self.cols]; self.rows];
         for (i, row) in self.board.iter().enumerate() {
             for (j, item) in row.iter().enumerate() {
                 if *item == TickType::Cross {
@@ -216,121 +378,411 @@ impl Tictactoe {
         vec![board_player_1, board_player_2]
     }
 
-    pub fn place_mark(&mut self, x: usize, y: usize) {
-        assert_eq!(self.board[x][y], TickType::Nil);
+    pub fn place_mark(&mut self, x: usize, y: usize) -> Result<(), ActionError> {
+        if self.board[x][y] != TickType::Nil {
+            return Err(ActionError::Occupied {
+                action: x * self.cols + y,
+            });
+        }
         self.board[x][y] = self.current_player.mark();
         self.current_player = self.current_player.other();
         self.turn += 1;
+        Ok(())
     }
 
-    pub fn win_condition(&self) -> bool {
-        if self.turn < 5 {
-            return false;
-        }
-        for i in 0..3 {
-            if Tictactoe::check_all_same(&self.board[i]) {
-                return true;
-            }
-            let temp_array = [self.board[0][i], self.board[1][i], self.board[2][i]];
-            if Tictactoe::check_all_same(&temp_array) {
-                return true;
-            }
+    // marks equal to `mark` following (row, col) in the direction
+    // (d_row, d_col), the cell itself excluded
+    fn run_length(
+        &self,
+        row: usize,
+        col: usize,
+        mark: TickType,
+        d_row: isize,
+        d_col: isize,
+    ) -> usize {
+        let mut length = 0;
+        let (mut r, mut c) = (row as isize + d_row, col as isize + d_col);
+        while r >= 0
+            && c >= 0
+            && (r as usize) < self.rows
+            && (c as usize) < self.cols
+            && self.board[r as usize][c as usize] == mark
+        {
+            length += 1;
+            r += d_row;
+            c += d_col;
         }
-        if Tictactoe::check_all_same(&[self.board[0][0], self.board[1][1], self.board[2][2]]) {
-            return true;
-        }
-        if Tictactoe::check_all_same(&[self.board[0][2], self.board[1][1], self.board[2][0]]) {
-            return true;
-        }
-        return false;
+        length
+    }
+
+    /// Whether `mark` on (row, col) would be part of `k` marks in a row,
+    /// whatever the cell holds now. Only the four lines through the cell
+    /// are looked at, so a move is checked in `O(k)`.
+    fn completes_line(&self, row: usize, col: usize, mark: TickType) -> bool {
+        DIRECTIONS.iter().any(|&(d_row, d_col)| {
+            1 + self.run_length(row, col, mark, d_row, d_col)
+                + self.run_length(row, col, mark, -d_row, -d_col)
+                >= self.k
+        })
+    }
+
+    /// Whether any player has `k` marks in a row on the board
+    #[allow(dead_code)]
+    pub fn win_condition(&self) -> bool {
+        self.turn + 1 >= 2 * self.k && self.line_owner().is_some()
     }
 
-    fn check_all_same(slice: &[TickType; 3]) -> bool {
-        let nil_present = slice.iter().any(|&x| x == TickType::Nil);
-        if nil_present {
-            return false;
+    /// The player with `k` marks in a row on the board, the first one found
+    /// on a board where both have one
+    fn line_owner(&self) -> Option<Player> {
+        for row in 0..self.rows {
+            for col in 0..self.cols {
+                let mark = self.board[row][col];
+                if mark != TickType::Nil && self.completes_line(row, col, mark) {
+                    return match mark {
+                        TickType::Cross => Some(Player::Crosses),
+                        _ => Some(Player::Noughts),
+                    };
+                }
+            }
         }
-        return slice[0] == slice[1] && slice[1] == slice[2];
+        None
     }
     #[allow(dead_code)]
     pub fn expert_action(&self) -> usize {
-        let winning = self.winning_move(self.current_player);
-        if winning.0 {
-            return winning.1 * 3 + winning.2;
+        if let Some(action) = self.winning_action(self.current_player) {
+            return action;
         }
-        let blocking = self.winning_move(self.current_player.other());
-        if blocking.0 {
-            return blocking.1 * 3 + blocking.2;
+        if let Some(action) = self.winning_action(self.current_player.other()) {
+            return action;
         }
-        if self.board[1][1] == TickType::Nil {
-            return 1 * 3 + 1;
+        let (center_row, center_col) = (self.rows / 2, self.cols / 2);
+        if self.board[center_row][center_col] == TickType::Nil {
+            return center_row * self.cols + center_col;
         }
         return self.random_action();
     }
 
-    fn winning_move(&self, player: Player) -> (bool, usize, usize) {
-        for (i, row) in self.board.iter().enumerate() {
-            let mut winnig_move = self.have_winning_move(row, &player);
-            if winnig_move.0 {
-                return (winnig_move.0, i, winnig_move.1);
-            }
-            let column = [self.board[0][i], self.board[1][i], self.board[2][i]];
-            winnig_move = self.have_winning_move(&column, &player);
-            if winnig_move.0 {
-                return (winnig_move.0, winnig_move.1, i);
-            }
+    // an empty cell where `player` would complete a line
+    fn winning_action(&self, player: Player) -> Option<usize> {
+        self.legal_actions().into_iter().find(|action| {
+            self.completes_line(action / self.cols, action % self.cols, player.mark())
+        })
+    }
+
+    pub fn random_action(&self) -> usize {
+        let mut rng = rand::thread_rng();
+        let action = self.legal_actions()[rng.gen_range(0..self.legal_actions().len())];
+        return action;
+    }
+    /// The symmetries of the board: the eight of the square, or the four
+    /// keeping the shape of a rectangular board
+    pub fn symmetries(&self) -> Vec<Symmetry> {
+        Symmetry::of_board(self.rows, self.cols)
+    }
+
+    /// Every symmetric equivalent of `observation`, planes laid out as in
+    /// `get_observation`, together with `policy` over the actions, the
+    /// pair itself first
+    pub fn symmetric_samples(
+        &self,
+        observation: &[Vec<Vec<usize>>],
+        policy: &[f32],
+    ) -> Vec<(Vec<Vec<Vec<usize>>>, Vec<f32>)> {
+        self.symmetries()
+            .into_iter()
+            .map(|symmetry| {
+                let planes = observation
+                    .iter()
+                    .map(|plane| symmetry.transform_grid(plane))
+                    .collect();
+                (
+                    planes,
+                    symmetry.transform_cells(policy, self.rows, self.cols),
+                )
+            })
+            .collect()
+    }
+
+    /// The symmetry taking the board to its representative, the image with
+    /// the smallest `get_board_int` in row order. Positions equal up to a
+    /// symmetry have the same representative.
+    pub fn canonical_symmetry(&self) -> Symmetry {
+        let board = self.get_board_int();
+        self.symmetries()
+            .into_iter()
+            .min_by_key(|symmetry| symmetry.transform_grid(&board))
+            .unwrap()
+    }
+
+    /// `action` moved by `symmetry`
+    pub fn transform_action(&self, action: usize, symmetry: Symmetry) -> usize {
+        let (row, col) =
+            symmetry.apply(action / self.cols, action % self.cols, self.rows, self.cols);
+        row * self.cols + col
+    }
+
+    #[allow(dead_code)]
+    pub fn print(&self) {
+        for row in self.board.iter() {
+            let cells: Vec<String> = row.iter().map(|item| item.to_string()).collect();
+            println!("{}", cells.join(" | "));
         }
-        let diagonal_1 = [self.board[0][0], self.board[1][1], self.board[2][2]];
-        let mut winnig_move = self.have_winning_move(&diagonal_1, &player);
-        let array: [usize; 3] = [2, 1, 0];
-        if winnig_move.0 {
-            return (winnig_move.0, winnig_move.1, winnig_move.1);
+    }
+}
+
+impl MctsGame for Tictactoe {
+    type Observation = Vec<Vec<Vec<usize>>>;
+
+    fn legal_actions(&self) -> Vec<usize> {
+        if self.done {
+            return Vec::new();
         }
-        let diagonal_2 = [self.board[0][2], self.board[1][1], self.board[2][0]];
-        winnig_move = self.have_winning_move(&diagonal_2, &player);
-        if winnig_move.0 {
-            return (winnig_move.0, winnig_move.1, array[winnig_move.1]);
+        Tictactoe::legal_actions(self)
+    }
+
+    fn apply_action(&mut self, action: usize) {
+        self.step(action).expect("only legal actions are searched");
+    }
+
+    fn to_play(&self) -> u8 {
+        Tictactoe::to_play(self)
+    }
+
+    fn is_terminal(&self) -> bool {
+        self.done
+    }
+
+    fn terminal_value(&self, player: u8) -> f32 {
+        match self.winner() {
+            Some(winner) if winner == player => 1.0,
+            Some(_) => -1.0,
+            None => 0.0,
         }
-        (false, 0, 0)
     }
 
-    fn have_winning_move(&self, vector: &[TickType; 3], player: &Player) -> (bool, usize) {
-        let mark_counter = self.mark_counter(vector, &player);
-        if mark_counter.0 == 2 && !mark_counter.1.is_empty() {
-            return (true, mark_counter.1[0]);
+    fn observation(&self) -> Self::Observation {
+        self.get_observation()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn play(rows: usize, cols: usize, k: usize, moves: &[usize]) -> Tictactoe {
+        let mut game = Tictactoe::new(rows, cols, k);
+        game.reset();
+        for action in moves {
+            game.step(*action).unwrap();
         }
-        return (false, 0);
+        game
     }
 
-    fn mark_counter(&self, vector: &[TickType; 3], player: &Player) -> (usize, Vec<usize>) {
-        let mark = player.mark();
-        let mut count = 0;
-        let mut empty_spaces: Vec<usize> = Vec::new();
-        for (i, item) in vector.iter().enumerate() {
-            if *item == mark {
-                count += 1;
-            } else if *item == TickType::Nil {
-                empty_spaces.push(i);
-            }
+    #[test]
+    fn three_in_a_row() {
+        // X X X
+        // O O .
+        // . . .
+        let game = play(3, 3, 3, &[0, 3, 1, 4, 2]);
+        assert!(game.done);
+        assert_eq!(0, game.get_winner_int());
+        assert!(game.win_condition());
+    }
+
+    #[test]
+    fn winner_is_the_player_who_completed_the_line() {
+        // O O O with crosses scattered
+        let mut game = play(3, 3, 3, &[0, 3, 1, 4, 8, 5]);
+        assert!(game.done);
+        assert_eq!(1, game.get_winner_int());
+        game.reset();
+        assert!(!game.done);
+        assert_eq!(2, game.get_winner_int());
+    }
+
+    #[test]
+    fn full_board_is_a_draw() {
+        // X O X
+        // X O O
+        // O X X
+        let game = play(3, 3, 3, &[0, 1, 2, 4, 3, 5, 7, 6, 8]);
+        assert!(game.done);
+        assert_eq!(2, game.get_winner_int());
+        assert!(!game.win_condition());
+    }
+
+    #[test]
+    fn first_player_is_the_same_after_new_and_reset() {
+        let mut game = Tictactoe::new(3, 3, 3);
+        assert_eq!(0, game.to_play());
+        game.step(4).unwrap();
+        game.reset();
+        assert_eq!(0, game.to_play());
+        game.set_first_player(Player::Noughts);
+        game.reset();
+        assert_eq!(1, game.to_play());
+        game.step(4).unwrap();
+        assert_eq!(TickType::Nought, game.board[1][1]);
+    }
+
+    #[test]
+    fn rewards_go_to_the_winner() {
+        let game = play(3, 3, 3, &[0, 3, 1, 4]);
+        assert_eq!([0.0, 0.0], game.get_rewards());
+        let mut game = play(3, 3, 3, &[0, 3, 1, 4, 6]);
+        let (_, rewards, done) = game.step(5).unwrap();
+        assert!(done);
+        assert_eq!(Some(1), game.winner());
+        assert_eq!([0.0, 1.0], rewards);
+        let game = play(3, 3, 3, &[0, 1, 2, 4, 3, 5, 7, 6, 8]);
+        assert_eq!(None, game.winner());
+        assert_eq!([0.5, 0.5], game.get_rewards());
+    }
+
+    #[test]
+    fn set_state_works_out_the_winner() {
+        let mut game = Tictactoe::new(3, 3, 3);
+        // X X X
+        // O O .
+        // . . .
+        game.set_state((1, vec![vec![-1, -1, -1], vec![1, 1, 0], vec![0, 0, 0]]));
+        assert!(game.done);
+        assert_eq!(Some(0), game.winner());
+        assert_eq!([1.0, 0.0], game.get_rewards());
+        game.set_state((0, vec![vec![-1, 1, 0], vec![0, 0, 0], vec![0, 0, 0]]));
+        assert!(!game.done);
+        assert_eq!(None, game.winner());
+    }
+
+    #[test]
+    fn undo_takes_back_the_last_mark() {
+        let mut game = play(3, 3, 3, &[0, 3, 1, 4]);
+        let before = game.get_state();
+        game.step(2).unwrap();
+        assert!(game.done);
+        assert!(game.undo());
+        assert_eq!(before, game.get_state());
+        assert_eq!(vec![0, 3, 1, 4], game.history);
+        while game.undo() {}
+        assert_eq!(0, game.turn);
+        assert_eq!(0, game.to_play());
+        assert_eq!(9, game.legal_actions().len());
+        // set_state starts a new history
+        game.set_state((before.1, before.2));
+        assert!(!game.undo());
+    }
+
+    #[test]
+    fn illegal_actions_are_refused() {
+        let mut game = play(3, 3, 3, &[4]);
+        let before = game.get_state();
+        assert_eq!(
+            Err(ActionError::OutOfBoard {
+                action: 9,
+                cells: 9
+            }),
+            game.step(9)
+        );
+        assert_eq!(Err(ActionError::Occupied { action: 4 }), game.step(4));
+        // nothing changed and noughts are still to move
+        assert_eq!(before, game.get_state());
+        assert_eq!(1, game.to_play());
+        let mut game = play(3, 3, 3, &[0, 3, 1, 4, 2]);
+        assert_eq!(Err(ActionError::GameOver), game.step(5));
+    }
+
+    #[test]
+    fn illegal_actions_forfeit_with_a_penalty() {
+        let mut game = Tictactoe::new(3, 3, 3);
+        game.set_illegal_action_penalty(Some(-1.0));
+        game.reset();
+        game.step(4).unwrap();
+        let (_, rewards, done) = game.step(4).unwrap();
+        assert!(done);
+        assert_eq!([0.0, -1.0], rewards);
+        assert_eq!(None, game.winner());
+        assert_eq!(Err(ActionError::GameOver), game.step(0));
+        // undo takes back the forfeit, not the mark before it
+        assert!(game.undo());
+        assert!(!game.done);
+        assert_eq!(vec![4], game.history);
+        assert_eq!([0.0; 2], game.step(0).unwrap().1);
+    }
+
+    #[test]
+    fn gomoku_diagonal() {
+        // crosses on the anti-diagonal from (2, 10) to (6, 6)
+        let crosses = [2 * 15 + 10, 3 * 15 + 9, 4 * 15 + 8, 5 * 15 + 7];
+        let noughts = [0, 1, 2, 3, 5];
+        let mut moves = Vec::new();
+        for (cross, nought) in crosses.iter().zip(noughts.iter()) {
+            moves.push(*cross);
+            moves.push(*nought);
         }
-        (count, empty_spaces)
+        let mut game = play(15, 15, 5, &moves);
+        assert!(!game.done);
+        // either end of the line wins
+        assert!([15 + 11, 6 * 15 + 6].contains(&game.expert_action()));
+        game.step(6 * 15 + 6).unwrap();
+        assert!(game.done);
+        assert_eq!(0, game.get_winner_int());
     }
 
-    pub fn random_action(&self) -> usize {
-        let mut rng = rand::thread_rng();
-        let action = self.legal_actions()[rng.gen_range(0..self.legal_actions().len())];
-        return action;
+    #[test]
+    fn four_needs_four() {
+        // X X X . on a 4x4 board is not a win, the expert blocks it
+        let mut game = play(4, 4, 4, &[0, 4, 1, 5, 2]);
+        assert!(!game.done);
+        assert_eq!(3, game.expert_action());
+        game.step(3).unwrap();
+        game.step(12).unwrap();
+        assert!(!game.done);
+        assert_eq!(4, game.get_observation()[0].len());
     }
-    #[allow(dead_code)]
-    pub fn print(&self) {
-        for row in self.board.iter() {
-            let mut row_string = String::new();
-            row_string.push_str(&row[0].to_string());
-            row_string.push_str(" | ");
-            row_string.push_str(&row[1].to_string());
-            row_string.push_str(" | ");
-            row_string.push_str(&row[2].to_string());
-            println!("{}", row_string);
+
+    #[test]
+    fn symmetric_samples_follow_the_game() {
+        let moves = [0, 5, 7];
+        let game = play(3, 3, 3, &moves);
+        let mut policy = vec![0.0; 9];
+        policy[1] = 1.0;
+        let samples = game.symmetric_samples(&game.get_observation(), &policy);
+        assert_eq!(8, samples.len());
+        assert_eq!(game.get_observation(), samples[0].0);
+        for (symmetry, (observation, policy)) in game.symmetries().into_iter().zip(samples) {
+            // replaying the moved moves gives the moved observation
+            let moved: Vec<usize> = moves
+                .iter()
+                .map(|action| game.transform_action(*action, symmetry))
+                .collect();
+            assert_eq!(play(3, 3, 3, &moved).get_observation(), observation);
+            assert_eq!(1.0, policy[game.transform_action(1, symmetry)]);
         }
     }
+
+    #[test]
+    fn canonical_board_is_shared_by_symmetric_positions() {
+        let canonical = |game: &Tictactoe| {
+            game.canonical_symmetry()
+                .transform_grid(&game.get_board_int())
+        };
+        // the four corner openings have one representative
+        let corners: Vec<Vec<Vec<isize>>> = [0, 2, 6, 8]
+            .iter()
+            .map(|action| canonical(&play(3, 3, 3, &[*action])))
+            .collect();
+        assert!(corners.iter().all(|board| *board == corners[0]));
+        assert_ne!(corners[0], canonical(&play(3, 3, 3, &[1])));
+        // a rectangle is not turned on its side
+        let game = play(2, 5, 2, &[6]);
+        assert_eq!(2, canonical(&game).len());
+    }
+
+    #[test]
+    fn rectangular_board_actions() {
+        let game = play(2, 5, 2, &[6]);
+        assert_eq!(9, game.legal_actions().len());
+        assert_eq!(TickType::Cross, game.board[1][1]);
+    }
 }
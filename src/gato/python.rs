@@ -1,21 +1,75 @@
-use super::Tictactoe;
+use super::{Player, Solver, Tictactoe, Ultimate, MAX_EMPTY_CELLS, ULTIMATE_SIZE};
+use crate::mcts::python::SearchResult;
+use crate::mcts::{self, Mcts, MctsConfig};
+use crate::symmetry::Symmetry;
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
+use pyo3::types::PyDict;
 
 // PYTHON MODULE
 // ---------------------------------------------------------
 // ---------------------------------------------------------
+/// Observation, reward of each player, done and winner
+type StepOutput = (Vec<Vec<Vec<usize>>>, Vec<f32>, bool, Option<u8>);
+/// Board, player to move and value of every legal action
+type ValueTableEntry = (Vec<Vec<isize>>, u8, Vec<(usize, i8)>);
+/// An observation and a policy over the actions
+type Sample = (Vec<Vec<Vec<usize>>>, Vec<f32>);
+
 #[pyclass]
 pub struct TictactoeEngine {
     game: Tictactoe,
+    mcts: Mcts,
+    solver: Solver,
 }
 
 #[pymethods]
 impl TictactoeEngine {
+    /// A board of `rows` by `cols` cells won with `k` marks in a row, by
+    /// default 3x3 tic-tac-toe, with `first_player` making the first mark
+    /// (0 for crosses, 1 for noughts). With an `illegal_action_penalty` an
+    /// illegal action ends the game with the penalty as the reward of the
+    /// player who made it, instead of raising `ValueError`.
     #[new]
-    fn new() -> Self {
-        TictactoeEngine {
-            game: Tictactoe::new(),
+    #[args(
+        rows = 3,
+        cols = 3,
+        k = 3,
+        first_player = 0,
+        illegal_action_penalty = "None"
+    )]
+    fn new(
+        rows: usize,
+        cols: usize,
+        k: usize,
+        first_player: u8,
+        illegal_action_penalty: Option<f32>,
+    ) -> PyResult<Self> {
+        if rows == 0 || cols == 0 || k == 0 || k > rows.max(cols) {
+            return Err(PyValueError::new_err(format!(
+                "invalid board {}x{} with {} in a row, k has to fit on the board",
+                rows, cols, k
+            )));
+        }
+        if first_player > 1 {
+            return Err(PyValueError::new_err(format!(
+                "invalid first player {}, 0 for crosses or 1 for noughts",
+                first_player
+            )));
         }
+        let mut game = Tictactoe::new(rows, cols, k);
+        game.set_first_player(if first_player == 0 {
+            Player::Crosses
+        } else {
+            Player::Noughts
+        });
+        game.set_illegal_action_penalty(illegal_action_penalty);
+        game.reset();
+        Ok(TictactoeEngine {
+            game,
+            mcts: Mcts::new(MctsConfig::default()),
+            solver: Solver::new(),
+        })
     }
 
     pub fn legal_actions(&self) -> PyResult<Vec<usize>> {
@@ -23,27 +77,49 @@ impl TictactoeEngine {
         Ok(legal_actions)
     }
 
-    pub fn step(&mut self, action: usize) -> PyResult<(Vec<Vec<Vec<usize>>>, f32, bool)> {
-        let (a, b, c) = self.game.step(action);
-        Ok((a, b, c))
+    /// Returns the observation, the rewards of both players indexed by
+    /// `to_play`, whether the game is over and the winner. Raises
+    /// `ValueError` for an illegal action, or once the game is over.
+    pub fn step(&mut self, action: usize) -> PyResult<StepOutput> {
+        let (observation, rewards, done) = self
+            .game
+            .step(action)
+            .map_err(|error| PyValueError::new_err(error.to_string()))?;
+        self.mcts.advance(action);
+        Ok((observation, rewards.to_vec(), done, self.game.winner()))
     }
 
-    pub fn get_state(&self) -> PyResult<(u8, u8, Vec<Vec<isize>>, u8, bool)> {
+    pub fn get_state(&self) -> PyResult<(usize, u8, Vec<Vec<isize>>, u8, bool)> {
         let (turn, to_play, board_int, winner_int, done) = self.game.get_state();
         Ok((turn, to_play, board_int, winner_int, done))
     }
 
+    /// Takes `(to_play, board_int)` laid out as in `get_state`, the winner
+    /// and the end of the game being worked out from the marks
     pub fn set_state(&mut self, state: (u8, Vec<Vec<isize>>)) -> PyResult<Vec<Vec<Vec<usize>>>> {
         let observation = self.game.set_state(state);
+        self.mcts.clear();
         Ok(observation)
     }
 
+    /// Takes back the last action and returns the new observation, or
+    /// `None` when there is no action to take back, e.g. right after
+    /// `reset` or `set_state`.
+    pub fn undo(&mut self) -> PyResult<Option<Vec<Vec<Vec<usize>>>>> {
+        if !self.game.undo() {
+            return Ok(None);
+        }
+        self.mcts.clear();
+        Ok(Some(self.game.get_observation()))
+    }
+
     pub fn to_play(&self) -> PyResult<u8> {
         Ok(self.game.to_play())
     }
 
     pub fn reset(&mut self) -> PyResult<Vec<Vec<Vec<usize>>>> {
         let observation = self.game.reset();
+        self.mcts.clear();
         Ok(observation)
     }
 
@@ -52,7 +128,237 @@ impl TictactoeEngine {
         Ok(action)
     }
 
+    /// An optimal action found by solving the game, `None` once the game
+    /// is over.
+    pub fn optimal_action(&mut self) -> PyResult<Option<usize>> {
+        require_solvable(&self.game)?;
+        Ok(self.solver.best_action(&self.game))
+    }
+
+    /// `[(action, value), ...]` for every legal action, the value being 1
+    /// for a win, 0 for a draw and -1 for a loss with perfect play.
+    pub fn action_values(&mut self) -> PyResult<Vec<(usize, i8)>> {
+        require_solvable(&self.game)?;
+        Ok(self.solver.action_values(&self.game))
+    }
+
+    /// `[(board_int, to_play, [(action, value), ...]), ...]` for every
+    /// position reachable from the start where the game is not over, with
+    /// boards laid out as in `get_state`.
+    pub fn value_table(&mut self) -> PyResult<Vec<ValueTableEntry>> {
+        let mut start = Tictactoe::new(self.game.rows, self.game.cols, self.game.k);
+        start.set_first_player(self.game.first_player);
+        start.reset();
+        require_solvable(&start)?;
+        Ok(self
+            .solver
+            .value_table(&start)
+            .into_iter()
+            .map(|(game, values)| (game.get_board_int(), game.to_play(), values))
+            .collect())
+    }
+
+    /// `[(observation, policy), ...]` for every symmetry of the board, the
+    /// given pair first, with `policy` holding one entry per cell.
+    pub fn symmetries(
+        &self,
+        observation: Vec<Vec<Vec<usize>>>,
+        policy: Vec<f32>,
+    ) -> PyResult<Vec<Sample>> {
+        let (rows, cols) = (self.game.rows, self.game.cols);
+        if observation
+            .iter()
+            .any(|plane| plane.len() != rows || plane.iter().any(|row| row.len() != cols))
+        {
+            return Err(PyValueError::new_err(format!(
+                "observation planes have to be {}x{}",
+                rows, cols
+            )));
+        }
+        if policy.len() != rows * cols {
+            return Err(PyValueError::new_err(format!(
+                "policy has {} entries, {} expected",
+                policy.len(),
+                rows * cols
+            )));
+        }
+        Ok(self.game.symmetric_samples(&observation, &policy))
+    }
+
+    /// `(to_play, board_int, symmetry)`: the representative of the current
+    /// position among its symmetric equivalents, board laid out as in
+    /// `get_state`, and the index of the symmetry taking the board there.
+    /// Actions of the representative map back with
+    /// `transform_action(action, symmetry, inverse=True)`.
+    pub fn canonical_state(&self) -> PyResult<(u8, Vec<Vec<isize>>, usize)> {
+        let symmetry = self.game.canonical_symmetry();
+        Ok((
+            self.game.to_play(),
+            symmetry.transform_grid(&self.game.get_board_int()),
+            symmetry.index(),
+        ))
+    }
+
+    /// `action` moved by the symmetry of index `symmetry`, or by its
+    /// inverse.
+    #[args(inverse = false)]
+    pub fn transform_action(
+        &self,
+        action: usize,
+        symmetry: usize,
+        inverse: bool,
+    ) -> PyResult<usize> {
+        let symmetry = Symmetry::from_index(symmetry)
+            .filter(|symmetry| self.game.symmetries().contains(symmetry))
+            .ok_or_else(|| {
+                PyValueError::new_err(format!("{} is not a symmetry of the board", symmetry))
+            })?;
+        if action >= self.game.rows * self.game.cols {
+            return Err(PyValueError::new_err(format!("invalid action {}", action)));
+        }
+        let symmetry = if inverse {
+            symmetry.inverse()
+        } else {
+            symmetry
+        };
+        Ok(self.game.transform_action(action, symmetry))
+    }
+
+    /// Replaces the search configuration and drops the current tree.
+    /// See `mcts::python::config_from_dict` for the accepted keys.
+    pub fn set_mcts_config(&mut self, config: &PyDict) -> PyResult<()> {
+        self.mcts = Mcts::new(mcts::python::config_from_dict(config)?);
+        Ok(())
+    }
+
+    /// Searches the current position and returns
+    /// `(best_action, [(action, visits), ...], root_value)`.
+    #[args(evaluator = "None", rollouts = 1)]
+    pub fn mcts_search(
+        &mut self,
+        py: Python,
+        evaluator: Option<PyObject>,
+        rollouts: usize,
+    ) -> PyResult<SearchResult> {
+        mcts::python::search(py, &mut self.mcts, &self.game, evaluator, rollouts)
+    }
+
     pub fn print(&self) {
         self.game.print();
     }
 }
+
+/// Ultimate tic-tac-toe: nine 3x3 boards where the cell played sends the
+/// opponent to the board at the same place, played on the cells of the
+/// whole 9x9 board, `row * 9 + col`
+#[pyclass]
+pub struct UltimateTictactoeEngine {
+    game: Ultimate,
+    mcts: Mcts,
+}
+
+#[pymethods]
+impl UltimateTictactoeEngine {
+    #[new]
+    fn new() -> Self {
+        UltimateTictactoeEngine {
+            game: Ultimate::new(),
+            mcts: Mcts::new(MctsConfig::default()),
+        }
+    }
+
+    pub fn legal_actions(&self) -> PyResult<Vec<usize>> {
+        Ok(self.game.legal_actions())
+    }
+
+    /// Returns the observation, the rewards of both players indexed by
+    /// `to_play`, whether the game is over and the winner. Raises
+    /// `ValueError` for an illegal action, or once the game is over.
+    pub fn step(&mut self, action: usize) -> PyResult<StepOutput> {
+        let (observation, rewards, done) = self
+            .game
+            .step(action)
+            .map_err(|error| PyValueError::new_err(error.to_string()))?;
+        self.mcts.advance(action);
+        Ok((observation, rewards.to_vec(), done, self.game.winner()))
+    }
+
+    /// `(turn, to_play, board_int, next_board, winner, done)`, `next_board`
+    /// being the board the player to move is sent to or `None` when any
+    /// open board will do
+    pub fn get_state(&self) -> PyResult<(usize, u8, Vec<Vec<isize>>, Option<usize>, u8, bool)> {
+        Ok(self.game.get_state())
+    }
+
+    /// Takes `(to_play, board_int, next_board)` laid out as in `get_state`
+    pub fn set_state(
+        &mut self,
+        state: (u8, Vec<Vec<isize>>, Option<usize>),
+    ) -> PyResult<Vec<Vec<Vec<usize>>>> {
+        let size = ULTIMATE_SIZE;
+        if state.1.len() != size || state.1.iter().any(|row| row.len() != size) {
+            return Err(PyValueError::new_err(format!(
+                "board has to be {}x{}",
+                size, size
+            )));
+        }
+        if state.2.map_or(false, |board| board >= size) {
+            return Err(PyValueError::new_err(format!(
+                "next board has to be below {}",
+                size
+            )));
+        }
+        let observation = self.game.set_state(state);
+        self.mcts.clear();
+        Ok(observation)
+    }
+
+    pub fn to_play(&self) -> PyResult<u8> {
+        Ok(self.game.to_play())
+    }
+
+    pub fn reset(&mut self) -> PyResult<Vec<Vec<Vec<usize>>>> {
+        let observation = self.game.reset();
+        self.mcts.clear();
+        Ok(observation)
+    }
+
+    pub fn expert_action(&self) -> PyResult<usize> {
+        Ok(self.game.expert_action())
+    }
+
+    /// Replaces the search configuration and drops the current tree.
+    /// See `mcts::python::config_from_dict` for the accepted keys.
+    pub fn set_mcts_config(&mut self, config: &PyDict) -> PyResult<()> {
+        self.mcts = Mcts::new(mcts::python::config_from_dict(config)?);
+        Ok(())
+    }
+
+    /// Searches the current position and returns
+    /// `(best_action, [(action, visits), ...], root_value)`.
+    #[args(evaluator = "None", rollouts = 1)]
+    pub fn mcts_search(
+        &mut self,
+        py: Python,
+        evaluator: Option<PyObject>,
+        rollouts: usize,
+    ) -> PyResult<SearchResult> {
+        mcts::python::search(py, &mut self.mcts, &self.game, evaluator, rollouts)
+    }
+
+    pub fn print(&self) {
+        self.game.print();
+    }
+}
+
+fn require_solvable(game: &Tictactoe) -> PyResult<()> {
+    let empty_cells = game.rows * game.cols - game.turn;
+    if empty_cells > MAX_EMPTY_CELLS {
+        Err(PyValueError::new_err(format!(
+            "the solver handles positions with at most {} empty cells, {} are empty",
+            MAX_EMPTY_CELLS, empty_cells
+        )))
+    } else {
+        Ok(())
+    }
+}
@@ -0,0 +1,193 @@
+use super::{TickType, Tictactoe};
+use std::collections::HashMap;
+
+/// Largest number of empty cells the solver takes on, 3x4 boards and
+/// smaller can be solved from the start
+pub const MAX_EMPTY_CELLS: usize = 12;
+
+/// Perfect play by negamax over the whole game tree. Every position met is
+/// kept in a transposition table, so that positions reached through
+/// different move orders are only solved once and later queries are
+/// answered from the table.
+#[derive(Default)]
+pub struct Solver {
+    // score of every position solved so far, for the player to move
+    table: HashMap<Vec<u8>, i32>,
+}
+
+// the cells and the player to move
+fn position_key(game: &Tictactoe) -> Vec<u8> {
+    let mut key: Vec<u8> = game
+        .board
+        .iter()
+        .flatten()
+        .map(|item| match item {
+            TickType::Nil => 0,
+            TickType::Cross => 1,
+            TickType::Nought => 2,
+        })
+        .collect();
+    key.push(game.to_play());
+    key
+}
+
+fn empty_cells(game: &Tictactoe) -> usize {
+    game.rows * game.cols - game.turn
+}
+
+impl Solver {
+    pub fn new() -> Solver {
+        Solver::default()
+    }
+
+    /// Score of `game` for the player to move: positive for a win,
+    /// negative for a loss and zero for a draw. Wins count for more the
+    /// more cells are left empty, so that the quickest win and the slowest
+    /// loss are preferred.
+    pub fn score(&mut self, game: &Tictactoe) -> i32 {
+        if game.done {
+            return match game.winner {
+                // the last mark won, the player to move has lost
+                Some(_) => -(empty_cells(game) as i32 + 1),
+                None => 0,
+            };
+        }
+        let key = position_key(game);
+        if let Some(&score) = self.table.get(&key) {
+            return score;
+        }
+        let mut best = i32::MIN;
+        for action in game.legal_actions() {
+            let mut next = game.clone();
+            next.step(action).expect("legal actions can be played");
+            best = best.max(-self.score(&next));
+        }
+        self.table.insert(key, best);
+        best
+    }
+
+    /// The game-theoretic value of every legal action for the player to
+    /// move: 1 for a win, 0 for a draw and -1 for a loss.
+    pub fn action_values(&mut self, game: &Tictactoe) -> Vec<(usize, i8)> {
+        self.action_scores(game)
+            .into_iter()
+            .map(|(action, score)| (action, score.signum() as i8))
+            .collect()
+    }
+
+    // the score of every legal action for the player to move
+    fn action_scores(&mut self, game: &Tictactoe) -> Vec<(usize, i32)> {
+        if game.done {
+            return Vec::new();
+        }
+        game.legal_actions()
+            .into_iter()
+            .map(|action| {
+                let mut next = game.clone();
+                next.step(action).expect("legal actions can be played");
+                (action, -self.score(&next))
+            })
+            .collect()
+    }
+
+    /// An optimal action, winning as fast or losing as slowly as possible,
+    /// the first one in action order among equals. `None` once the game is
+    /// over.
+    pub fn best_action(&mut self, game: &Tictactoe) -> Option<usize> {
+        let scores = self.action_scores(game);
+        let best = scores.iter().map(|&(_, score)| score).max()?;
+        scores
+            .into_iter()
+            .find(|&(_, score)| score == best)
+            .map(|(action, _)| action)
+    }
+
+    /// The action values of every position reachable from `game` where the
+    /// game is not over, `game` first.
+    pub fn value_table(&mut self, game: &Tictactoe) -> Vec<(Tictactoe, Vec<(usize, i8)>)> {
+        let mut seen = HashMap::new();
+        let mut stack = vec![game.clone()];
+        let mut table = Vec::new();
+        while let Some(game) = stack.pop() {
+            if game.done || seen.insert(position_key(&game), ()).is_some() {
+                continue;
+            }
+            let values = self.action_values(&game);
+            for &(action, _) in values.iter().rev() {
+                let mut next = game.clone();
+                next.step(action).expect("legal actions can be played");
+                stack.push(next);
+            }
+            table.push((game, values));
+        }
+        table
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn play(moves: &[usize]) -> Tictactoe {
+        let mut game = Tictactoe::new(3, 3, 3);
+        game.reset();
+        for action in moves {
+            game.step(*action).unwrap();
+        }
+        game
+    }
+
+    #[test]
+    fn tictactoe_is_a_draw() {
+        let mut solver = Solver::new();
+        let game = play(&[]);
+        assert_eq!(0, solver.score(&game));
+        let values = solver.action_values(&game);
+        assert_eq!(9, values.len());
+        assert!(values.iter().all(|&(_, value)| value == 0));
+    }
+
+    #[test]
+    fn edge_reply_to_a_corner_loses() {
+        // X O .
+        // . . .
+        // . . .
+        let mut solver = Solver::new();
+        let game = play(&[0, 1]);
+        assert!(solver.score(&game) > 0);
+        let values = solver.action_values(&game);
+        assert!(values.iter().any(|&(_, value)| value == 1));
+    }
+
+    #[test]
+    fn takes_the_quickest_win() {
+        // X X .
+        // O O .
+        // X . O
+        let mut solver = Solver::new();
+        let game = play(&[0, 3, 1, 4, 6, 8]);
+        assert_eq!(Some(2), solver.best_action(&game));
+        assert!(solver.action_values(&game).contains(&(2, 1)));
+        // O wins on 5 after anything but 2 or 5
+        assert!(solver.action_values(&game).contains(&(7, -1)));
+    }
+
+    #[test]
+    fn value_table_covers_every_position() {
+        let mut solver = Solver::new();
+        let table = solver.value_table(&play(&[]));
+        // 5478 legal positions, 958 of them finished
+        assert_eq!(4520, table.len());
+        assert_eq!(0, table[0].0.turn);
+        // every position but the first one, only its actions are scored
+        assert_eq!(4519, solver.table.len());
+    }
+
+    #[test]
+    fn finished_game_has_no_action() {
+        let mut solver = Solver::new();
+        let game = play(&[0, 3, 1, 4, 2]);
+        assert_eq!(None, solver.best_action(&game));
+        assert!(solver.action_values(&game).is_empty());
+    }
+}
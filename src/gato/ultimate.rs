@@ -0,0 +1,519 @@
+use super::{ActionError, Player, StepOutcome, TickType, Tictactoe};
+use crate::mcts::MctsGame;
+use rand::Rng;
+
+// Ultimate tic-tac-toe
+// ---------------------------------------------------------
+// Nine tic-tac-toe boards laid out as the cells of a meta-board. The cell
+// played in a board sends the opponent to the board at the same place of
+// the meta-board, or anywhere when that board is already won or full.
+// Winning a board takes its cell of the meta-board, and three boards in a
+// row win the game. The game is drawn once every board is closed without
+// three in a row.
+//
+// Actions are the cells of the whole 9x9 board, `row * 9 + col`.
+
+/// Cells along a side of a board, and boards along a side of the
+/// meta-board
+const SIDE: usize = 3;
+/// Cells along a side of the whole board
+pub const ULTIMATE_SIZE: usize = SIDE * SIDE;
+
+// the board an action is played in and the cell of that board
+fn board_and_cell(action: usize) -> (usize, usize) {
+    let (row, col) = (action / ULTIMATE_SIZE, action % ULTIMATE_SIZE);
+    (
+        (row / SIDE) * SIDE + col / SIDE,
+        (row % SIDE) * SIDE + col % SIDE,
+    )
+}
+
+fn to_action(board: usize, cell: usize) -> usize {
+    let row = (board / SIDE) * SIDE + cell / SIDE;
+    let col = (board % SIDE) * SIDE + cell % SIDE;
+    row * ULTIMATE_SIZE + col
+}
+
+#[derive(Debug, Clone)]
+pub struct Ultimate {
+    /// The nine boards, row after row of the meta-board
+    pub boards: Vec<Tictactoe>,
+    /// Cell `i` is marked by the player who won board `i`
+    pub meta: Tictactoe,
+    pub turn: usize,
+    pub current_player: Player,
+    /// The board the player to move is sent to, `None` when any open board
+    /// will do
+    pub next_board: Option<usize>,
+    pub winner: Option<Player>,
+    pub done: bool,
+}
+
+impl Ultimate {
+    pub fn new() -> Ultimate {
+        let mut board = Tictactoe::new(SIDE, SIDE, SIDE);
+        board.reset();
+        Ultimate {
+            boards: vec![board.clone(); SIDE * SIDE],
+            meta: board,
+            turn: 0,
+            current_player: Player::Crosses,
+            next_board: None,
+            winner: None,
+            done: false,
+        }
+    }
+
+    /// Turn, player to move, the 9x9 board laid out as in
+    /// `Tictactoe::get_state`, the board the player to move is sent to,
+    /// winner and done
+    pub fn get_state(&self) -> (usize, u8, Vec<Vec<isize>>, Option<usize>, u8, bool) {
+        (
+            self.turn,
+            self.to_play(),
+            self.get_board_int(),
+            self.next_board,
+            self.winner().unwrap_or(2),
+            self.done,
+        )
+    }
+
+    /// `to_play` index of the winner, `None` for a draw or a game in
+    /// progress
+    pub fn winner(&self) -> Option<u8> {
+        self.meta.winner()
+    }
+
+    /// Sets the player to move, the 9x9 board and the board the player to
+    /// move is sent to. Won boards, the winner and the end of the game are
+    /// worked out from the marks.
+    pub fn set_state(
+        &mut self,
+        state: (u8, Vec<Vec<isize>>, Option<usize>),
+    ) -> Vec<Vec<Vec<usize>>> {
+        let (to_play, board_int, next_board) = state;
+        let cell_int = |action: usize| {
+            board_int
+                .get(action / ULTIMATE_SIZE)
+                .and_then(|row| row.get(action % ULTIMATE_SIZE))
+                .copied()
+                .unwrap_or(0)
+        };
+        let mut meta_int = vec![vec![0; SIDE]; SIDE];
+        for (i, board) in self.boards.iter_mut().enumerate() {
+            let sub_int = (0..SIDE)
+                .map(|row| {
+                    (0..SIDE)
+                        .map(|col| cell_int(to_action(i, row * SIDE + col)))
+                        .collect()
+                })
+                .collect();
+            board.set_state((0, sub_int));
+            meta_int[i / SIDE][i % SIDE] = match board.winner {
+                Some(Player::Noughts) => 1,
+                Some(Player::Crosses) => -1,
+                None => 0,
+            };
+        }
+        self.meta.set_state((0, meta_int));
+
+        self.turn = self.boards.iter().map(|board| board.turn).sum();
+        self.current_player = match to_play {
+            0 => Player::Crosses,
+            _ => Player::Noughts,
+        };
+        self.winner = self.meta.winner;
+        self.done = self.winner.is_some() || self.boards.iter().all(|board| board.done);
+        self.next_board =
+            next_board.filter(|&board| board < SIDE * SIDE && !self.boards[board].done);
+        self.get_observation()
+    }
+
+    pub fn to_play(&self) -> u8 {
+        match self.current_player {
+            Player::Crosses => 0,
+            Player::Noughts => 1,
+        }
+    }
+
+    pub fn reset(&mut self) -> Vec<Vec<Vec<usize>>> {
+        *self = Ultimate::new();
+        self.get_observation()
+    }
+
+    /// Marks the cell `action` for the player to move and returns the
+    /// observation, the rewards of both players indexed by `to_play`, as
+    /// in `Tictactoe::get_rewards`, and whether the game is over. Actions
+    /// that are not legal are refused.
+    pub fn step(&mut self, action: usize) -> Result<StepOutcome, ActionError> {
+        if self.done {
+            return Err(ActionError::GameOver);
+        }
+        if action >= ULTIMATE_SIZE * ULTIMATE_SIZE {
+            return Err(ActionError::OutOfBoard {
+                action,
+                cells: ULTIMATE_SIZE * ULTIMATE_SIZE,
+            });
+        }
+        let (board, cell) = board_and_cell(action);
+        match self.next_board {
+            Some(next) if next != board => {
+                return Err(ActionError::WrongBoard {
+                    action,
+                    board: next,
+                })
+            }
+            _ if self.boards[board].done => return Err(ActionError::ClosedBoard { action, board }),
+            _ => {}
+        }
+        let player = self.current_player;
+        self.boards[board].current_player = player;
+        self.boards[board]
+            .step(cell)
+            .map_err(|_| ActionError::Occupied { action })?;
+        if self.boards[board].winner.is_some() {
+            self.meta.current_player = player;
+            self.meta.step(board).expect("a board is only won once");
+            self.winner = self.meta.winner;
+        }
+        self.turn += 1;
+        self.current_player = player.other();
+        self.next_board = Some(cell).filter(|&next| !self.boards[next].done);
+        self.done = self.winner.is_some() || self.boards.iter().all(|board| board.done);
+        let rewards = match self.winner() {
+            Some(winner) => {
+                let mut rewards = [0.0; 2];
+                rewards[winner as usize] = 1.0;
+                rewards
+            }
+            None if self.done => [0.5; 2],
+            None => [0.0; 2],
+        };
+        Ok((self.get_observation(), rewards, self.done))
+    }
+
+    /// The empty cells of the board the player to move is sent to, or of
+    /// every open board
+    pub fn legal_actions(&self) -> Vec<usize> {
+        if self.done {
+            return Vec::new();
+        }
+        let mut legal_actions = Vec::new();
+        for (i, board) in self.boards.iter().enumerate() {
+            if board.done || self.next_board.map_or(false, |next| next != i) {
+                continue;
+            }
+            for cell in board.legal_actions() {
+                legal_actions.push(to_action(i, cell));
+            }
+        }
+        legal_actions.sort_unstable();
+        legal_actions
+    }
+
+    // Three 9x9 planes: crosses, noughts and the cells the player to move
+    // may play
+    fn get_observation(&self) -> Vec<Vec<Vec<usize>>> {
+        let mut planes = vec![vec![vec![0; ULTIMATE_SIZE]; ULTIMATE_SIZE]; 3];
+        for action in 0..ULTIMATE_SIZE * ULTIMATE_SIZE {
+            let (board, cell) = board_and_cell(action);
+            let (row, col) = (action / ULTIMATE_SIZE, action % ULTIMATE_SIZE);
+            match self.boards[board].board[cell / SIDE][cell % SIDE] {
+                TickType::Cross => planes[0][row][col] = 1,
+                TickType::Nought => planes[1][row][col] = 1,
+                TickType::Nil => {}
+            }
+        }
+        for action in self.legal_actions() {
+            planes[2][action / ULTIMATE_SIZE][action % ULTIMATE_SIZE] = 1;
+        }
+        planes
+    }
+
+    fn get_board_int(&self) -> Vec<Vec<isize>> {
+        let mut board_int = vec![vec![0; ULTIMATE_SIZE]; ULTIMATE_SIZE];
+        for (i, board) in self.boards.iter().enumerate() {
+            for (cell, item) in board.get_board_int().into_iter().flatten().enumerate() {
+                let action = to_action(i, cell);
+                board_int[action / ULTIMATE_SIZE][action % ULTIMATE_SIZE] = item;
+            }
+        }
+        board_int
+    }
+
+    // whether `player` playing `action` would win the board it is played in
+    fn wins_board(&self, action: usize, player: Player) -> bool {
+        let (board, cell) = board_and_cell(action);
+        self.boards[board].completes_line(cell / SIDE, cell % SIDE, player.mark())
+    }
+
+    /// Wins the game if a board does it, else wins a board, else blocks
+    /// the opponent from winning this one, else plays at random among the
+    /// moves not sending the opponent where they can win a board.
+    pub fn expert_action(&self) -> usize {
+        let me = self.current_player;
+        let actions = self.legal_actions();
+        let winning: Vec<usize> = actions
+            .iter()
+            .copied()
+            .filter(|&action| self.wins_board(action, me))
+            .collect();
+        if let Some(&action) = winning.iter().find(|&&action| {
+            let (board, _) = board_and_cell(action);
+            self.meta
+                .completes_line(board / SIDE, board % SIDE, me.mark())
+        }) {
+            return action;
+        }
+        if let Some(&action) = winning.first() {
+            return action;
+        }
+        if let Some(&action) = actions
+            .iter()
+            .find(|&&action| self.wins_board(action, me.other()))
+        {
+            return action;
+        }
+        let safe: Vec<usize> = actions
+            .iter()
+            .copied()
+            .filter(|&action| {
+                let mut next = self.clone();
+                next.step(action).expect("legal actions can be played");
+                !next
+                    .legal_actions()
+                    .into_iter()
+                    .any(|reply| next.wins_board(reply, me.other()))
+            })
+            .collect();
+        let candidates = if safe.is_empty() { actions } else { safe };
+        let mut rng = rand::thread_rng();
+        candidates[rng.gen_range(0..candidates.len())]
+    }
+
+    pub fn print(&self) {
+        let board_int = self.get_board_int();
+        for (row, cells) in board_int.iter().enumerate() {
+            if row > 0 && row % SIDE == 0 {
+                println!("------+-------+------");
+            }
+            let blocks: Vec<String> = cells
+                .chunks(SIDE)
+                .map(|block| {
+                    let marks: Vec<&str> = block
+                        .iter()
+                        .map(|item| match item {
+                            1 => "O",
+                            -1 => "X",
+                            _ => ".",
+                        })
+                        .collect();
+                    marks.join(" ")
+                })
+                .collect();
+            println!("{}", blocks.join(" | "));
+        }
+    }
+}
+
+impl MctsGame for Ultimate {
+    type Observation = Vec<Vec<Vec<usize>>>;
+
+    fn legal_actions(&self) -> Vec<usize> {
+        Ultimate::legal_actions(self)
+    }
+
+    fn apply_action(&mut self, action: usize) {
+        self.step(action).expect("only legal actions are searched");
+    }
+
+    fn to_play(&self) -> u8 {
+        Ultimate::to_play(self)
+    }
+
+    fn is_terminal(&self) -> bool {
+        self.done
+    }
+
+    fn terminal_value(&self, player: u8) -> f32 {
+        match self.winner() {
+            Some(winner) if winner == player => 1.0,
+            Some(_) => -1.0,
+            None => 0.0,
+        }
+    }
+
+    fn observation(&self) -> Self::Observation {
+        self.get_observation()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn play(moves: &[usize]) -> Ultimate {
+        let mut game = Ultimate::new();
+        for action in moves {
+            game.step(*action).unwrap();
+        }
+        game
+    }
+
+    // a 9x9 board with crosses on `crosses` and noughts on `noughts`
+    fn board_int(crosses: &[usize], noughts: &[usize]) -> Vec<Vec<isize>> {
+        let mut board = vec![vec![0; ULTIMATE_SIZE]; ULTIMATE_SIZE];
+        for &action in crosses {
+            board[action / ULTIMATE_SIZE][action % ULTIMATE_SIZE] = -1;
+        }
+        for &action in noughts {
+            board[action / ULTIMATE_SIZE][action % ULTIMATE_SIZE] = 1;
+        }
+        board
+    }
+
+    #[test]
+    fn actions_map_to_boards_and_cells() {
+        // the center of the top middle board
+        assert_eq!((1, 4), board_and_cell(ULTIMATE_SIZE + 4));
+        for action in 0..ULTIMATE_SIZE * ULTIMATE_SIZE {
+            let (board, cell) = board_and_cell(action);
+            assert_eq!(action, to_action(board, cell));
+        }
+    }
+
+    #[test]
+    fn the_cell_played_sends_to_a_board() {
+        let game = Ultimate::new();
+        assert_eq!(81, game.legal_actions().len());
+        // the top right cell of the top left board sends to the top right
+        // board
+        let game = play(&[2]);
+        assert_eq!(Some(2), game.next_board);
+        let legal_actions = game.legal_actions();
+        assert_eq!(9, legal_actions.len());
+        assert!(legal_actions
+            .iter()
+            .all(|&action| board_and_cell(action).0 == 2));
+        assert_eq!(1, game.get_observation()[2][0][6]);
+        assert_eq!(0, game.get_observation()[2][0][0]);
+    }
+
+    #[test]
+    fn actions_outside_the_board_to_play_are_refused() {
+        let mut game = play(&[2]);
+        assert_eq!(
+            Err(ActionError::WrongBoard {
+                action: 0,
+                board: 2
+            }),
+            game.step(0)
+        );
+        assert_eq!(
+            Err(ActionError::OutOfBoard {
+                action: 81,
+                cells: 81
+            }),
+            game.step(81)
+        );
+        assert_eq!(Some(2), game.next_board);
+        assert_eq!(1, game.to_play());
+
+        // the top left cell sends noughts back to the top left board
+        let mut game = play(&[0]);
+        assert_eq!(Err(ActionError::Occupied { action: 0 }), game.step(0));
+        assert_eq!(1, game.to_play());
+        assert!(game.step(1).is_ok());
+    }
+
+    #[test]
+    fn a_won_board_sends_anywhere() {
+        // crosses hold two cells of the top left board, noughts are sent
+        // there and X wins it next
+        let crosses = [0, 1];
+        let noughts = [to_action(4, 0), to_action(5, 0)];
+        let mut game = Ultimate::new();
+        game.set_state((0, board_int(&crosses, &noughts), Some(0)));
+        assert!(game.wins_board(2, Player::Crosses));
+        game.step(2).unwrap();
+        assert_eq!(Some(Player::Crosses), game.boards[0].winner);
+        assert_eq!(TickType::Cross, game.meta.board[0][0]);
+        // the top right cell sends to the top right board, still open
+        assert_eq!(Some(2), game.next_board);
+        game.step(to_action(2, 0)).unwrap();
+        // sent back to the won board, any open board will do
+        assert_eq!(None, game.next_board);
+        let legal_actions = game.legal_actions();
+        assert!(legal_actions
+            .iter()
+            .all(|&action| board_and_cell(action).0 != 0));
+        // the eight other boards less the three marks in them
+        assert_eq!(8 * 9 - 3, legal_actions.len());
+    }
+
+    #[test]
+    fn three_boards_in_a_row_win() {
+        // crosses won the top left and top middle boards and can take the
+        // top right one
+        let mut crosses: Vec<usize> = (0..SIDE).map(|cell| to_action(0, cell)).collect();
+        crosses.extend((0..SIDE).map(|cell| to_action(1, cell)));
+        crosses.extend(&[to_action(2, 4), to_action(2, 8)]);
+        let noughts = [
+            to_action(3, 0),
+            to_action(3, 1),
+            to_action(4, 0),
+            to_action(4, 1),
+            to_action(5, 0),
+            to_action(5, 1),
+            to_action(6, 0),
+            to_action(6, 1),
+        ];
+        let mut game = Ultimate::new();
+        game.set_state((0, board_int(&crosses, &noughts), Some(2)));
+        assert_eq!(Some(Player::Crosses), game.boards[1].winner);
+        assert!(!game.done);
+        assert_eq!(to_action(2, 0), game.expert_action());
+        let (_, rewards, done) = game.step(to_action(2, 0)).unwrap();
+        assert!(done);
+        assert_eq!([1.0, 0.0], rewards);
+        assert_eq!(0, game.get_state().4);
+        assert!(game.legal_actions().is_empty());
+    }
+
+    #[test]
+    fn expert_blocks_a_board() {
+        // noughts threaten the middle row of the center board, where
+        // crosses are sent
+        let noughts = [to_action(4, 3), to_action(4, 4)];
+        let crosses = [to_action(0, 0), to_action(8, 8)];
+        let mut game = Ultimate::new();
+        game.set_state((0, board_int(&crosses, &noughts), Some(4)));
+        assert_eq!(to_action(4, 5), game.expert_action());
+    }
+
+    #[test]
+    fn random_games_end() {
+        for _ in 0..50 {
+            let mut game = Ultimate::new();
+            while !game.done {
+                assert!(!game.legal_actions().is_empty());
+                let action = game.expert_action();
+                game.step(action).unwrap();
+            }
+            assert!(game.turn <= 81);
+            assert!(game.winner.is_some() || game.boards.iter().all(|board| board.done));
+        }
+    }
+
+    #[test]
+    fn state_round_trip() {
+        let game = play(&[40, 30, 10, 31, 12]);
+        let (turn, to_play, board, next_board, winner, done) = game.get_state();
+        assert_eq!(5, turn);
+        let mut other = Ultimate::new();
+        other.set_state((to_play, board, next_board));
+        assert_eq!(game.get_state(), other.get_state());
+        assert_eq!(game.legal_actions(), other.legal_actions());
+        assert_eq!((2, false), (winner, done));
+    }
+}
@@ -8,17 +8,19 @@ mod blackjack;
 mod checkers;
 mod chess;
 mod gato;
+mod mcts;
 mod poker;
+mod symmetry;
 
 // GYMS
 #[cfg(feature = "python")]
 use blackjack::python::BlackjackEngine;
 #[cfg(feature = "python")]
-use checkers::python::CheckersEngine;
+use checkers::python::{CheckersEngine, IllegalMoveError, InvalidStateError, MustJumpError};
 #[cfg(feature = "python")]
 use chess::python::ChessEngine;
 #[cfg(feature = "python")]
-use gato::python::TictactoeEngine;
+use gato::python::{TictactoeEngine, UltimateTictactoeEngine};
 #[cfg(feature = "python")]
 use poker::python::PokerEngine;
 
@@ -27,12 +29,16 @@ use poker::python::PokerEngine;
 // ---------------------------------------------------------
 #[cfg(feature = "python")]
 #[pymodule]
-fn zarena(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
+fn zarena(py: Python<'_>, m: &PyModule) -> PyResult<()> {
     m.add_class::<TictactoeEngine>()?;
+    m.add_class::<UltimateTictactoeEngine>()?;
     m.add_class::<BlackjackEngine>()?;
     m.add_class::<PokerEngine>()?;
     m.add_class::<ChessEngine>()?;
     m.add_class::<CheckersEngine>()?;
+    m.add("IllegalMoveError", py.get_type::<IllegalMoveError>())?;
+    m.add("MustJumpError", py.get_type::<MustJumpError>())?;
+    m.add("InvalidStateError", py.get_type::<InvalidStateError>())?;
 
     Ok(())
 }
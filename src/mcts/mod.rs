@@ -0,0 +1,569 @@
+#[cfg(feature = "python")]
+pub mod python;
+
+use rand::Rng;
+use std::collections::HashSet;
+use std::convert::Infallible;
+
+/// A two player, zero-sum, perfect information game that can be searched
+/// with `Mcts`. Actions are the same integers the Python engines expose in
+/// `legal_actions`/`step`.
+pub trait MctsGame: Clone {
+    type Observation;
+
+    fn legal_actions(&self) -> Vec<usize>;
+
+    /// Applies a legal action. The search only calls this with actions
+    /// returned by `legal_actions`.
+    fn apply_action(&mut self, action: usize);
+
+    fn to_play(&self) -> u8;
+
+    fn is_terminal(&self) -> bool;
+
+    /// Score of a finished game from the point of view of `player`:
+    /// `1.0` for a win, `-1.0` for a loss and `0.0` for a draw.
+    fn terminal_value(&self, player: u8) -> f32;
+
+    fn observation(&self) -> Self::Observation;
+}
+
+/// The result of evaluating a leaf. `priors` are aligned with the order of
+/// `legal_actions()` of the evaluated position, and `value` is in `[-1, 1]`
+/// from the point of view of the player to move there.
+#[derive(Debug, Clone)]
+pub struct Evaluation {
+    pub priors: Vec<f32>,
+    pub value: f32,
+}
+
+/// Hook used by the search to score leaves, e.g. a neural network.
+/// Leaves are handed over in batches of up to `MctsConfig::batch_size`.
+pub trait Evaluator<G: MctsGame> {
+    type Error;
+
+    fn evaluate(&mut self, games: &[G]) -> Result<Vec<Evaluation>, Self::Error>;
+}
+
+/// Fallback evaluator: uniform priors and the average result of random
+/// playouts.
+pub struct RolloutEvaluator {
+    pub rollouts: usize,
+}
+
+impl RolloutEvaluator {
+    pub fn new(rollouts: usize) -> RolloutEvaluator {
+        RolloutEvaluator {
+            rollouts: rollouts.max(1),
+        }
+    }
+
+    fn rollout<G: MctsGame>(game: &G) -> f32 {
+        let player = game.to_play();
+        let mut game = game.clone();
+        let mut rng = rand::thread_rng();
+        while !game.is_terminal() {
+            let actions = game.legal_actions();
+            if actions.is_empty() {
+                break;
+            }
+            game.apply_action(actions[rng.gen_range(0..actions.len())]);
+        }
+        game.terminal_value(player)
+    }
+}
+
+impl<G: MctsGame> Evaluator<G> for RolloutEvaluator {
+    type Error = Infallible;
+
+    fn evaluate(&mut self, games: &[G]) -> Result<Vec<Evaluation>, Infallible> {
+        Ok(games
+            .iter()
+            .map(|game| {
+                let n_actions = game.legal_actions().len().max(1);
+                let total: f32 = (0..self.rollouts).map(|_| Self::rollout(game)).sum();
+                Evaluation {
+                    priors: vec![1.0 / n_actions as f32; n_actions],
+                    value: total / self.rollouts as f32,
+                }
+            })
+            .collect())
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Selection {
+    /// UCB1 applied to trees, priors are ignored
+    Uct,
+
+    /// AlphaZero style selection weighted by the evaluator priors
+    Puct,
+}
+
+#[derive(Debug, Clone)]
+pub struct MctsConfig {
+    pub simulations: usize,
+    pub selection: Selection,
+    pub exploration: f32,
+    /// Dirichlet noise mixed into the root priors. Disabled when
+    /// `dirichlet_fraction` is `0.0`.
+    pub dirichlet_alpha: f32,
+    pub dirichlet_fraction: f32,
+    /// Each pending visit counts as this many losses while a batch of
+    /// leaves is being collected.
+    pub virtual_loss: f32,
+    pub batch_size: usize,
+    /// Keep the subtree of the played action between searches
+    pub reuse_tree: bool,
+}
+
+impl Default for MctsConfig {
+    fn default() -> MctsConfig {
+        MctsConfig {
+            simulations: 800,
+            selection: Selection::Puct,
+            exploration: 1.25,
+            dirichlet_alpha: 0.3,
+            dirichlet_fraction: 0.0,
+            virtual_loss: 1.0,
+            batch_size: 1,
+            reuse_tree: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    action: usize,
+    prior: f32,
+    to_play: u8,
+    visits: u32,
+    virtual_visits: u32,
+    // sum of the values seen from the point of view of `to_play`
+    value_sum: f32,
+    expanded: bool,
+    terminal: bool,
+    children: Vec<usize>,
+}
+
+impl Node {
+    fn new(action: usize, prior: f32) -> Node {
+        Node {
+            action,
+            prior,
+            to_play: 0,
+            visits: 0,
+            virtual_visits: 0,
+            value_sum: 0.0,
+            expanded: false,
+            terminal: false,
+            children: Vec::new(),
+        }
+    }
+}
+
+/// Monte Carlo Tree Search over any `MctsGame`. The tree lives in an arena
+/// so it can be kept between moves with `advance`.
+#[derive(Debug, Clone)]
+pub struct Mcts {
+    pub config: MctsConfig,
+    nodes: Vec<Node>,
+    root_noised: bool,
+}
+
+const ROOT: usize = 0;
+
+impl Mcts {
+    pub fn new(config: MctsConfig) -> Mcts {
+        Mcts {
+            config,
+            nodes: vec![Node::new(0, 1.0)],
+            root_noised: false,
+        }
+    }
+
+    /// Drops the whole tree, e.g. after `reset` or `set_state`
+    pub fn clear(&mut self) {
+        self.nodes = vec![Node::new(0, 1.0)];
+        self.root_noised = false;
+    }
+
+    /// Moves the root to the child reached by `action`, keeping its
+    /// statistics if tree reuse is enabled.
+    pub fn advance(&mut self, action: usize) {
+        let child = self.nodes[ROOT]
+            .children
+            .iter()
+            .copied()
+            .find(|&child| self.nodes[child].action == action);
+        match child {
+            Some(child) if self.config.reuse_tree => {
+                let mut nodes = Vec::new();
+                self.copy_subtree(child, &mut nodes);
+                self.nodes = nodes;
+                self.root_noised = false;
+            }
+            _ => self.clear(),
+        }
+    }
+
+    fn copy_subtree(&self, index: usize, nodes: &mut Vec<Node>) -> usize {
+        let new_index = nodes.len();
+        nodes.push(self.nodes[index].clone());
+        let children: Vec<usize> = self.nodes[index]
+            .children
+            .iter()
+            .map(|&child| self.copy_subtree(child, nodes))
+            .collect();
+        nodes[new_index].children = children;
+        new_index
+    }
+
+    /// Runs `config.simulations` simulations from `game`, which must be the
+    /// position the tree root corresponds to.
+    pub fn search<G, E>(&mut self, game: &G, evaluator: &mut E) -> Result<(), E::Error>
+    where
+        G: MctsGame,
+        E: Evaluator<G>,
+    {
+        if game.is_terminal() {
+            return Ok(());
+        }
+        if !self.nodes[ROOT].expanded {
+            let evaluation = evaluator.evaluate(std::slice::from_ref(game))?;
+            self.expand(ROOT, game, &evaluation[0]);
+        }
+        if !self.root_noised && self.config.dirichlet_fraction > 0.0 {
+            self.add_dirichlet_noise();
+        }
+        self.root_noised = true;
+
+        let batch_size = self.config.batch_size.max(1);
+        let mut simulations = 0;
+        while simulations < self.config.simulations {
+            let mut paths = Vec::new();
+            let mut leaves = Vec::new();
+            let mut pending = HashSet::new();
+
+            while paths.len() < batch_size && simulations + paths.len() < self.config.simulations {
+                let (path, leaf) = self.select_leaf(game);
+                let leaf_index = *path.last().unwrap();
+                if self.nodes[leaf_index].terminal {
+                    let to_play = self.nodes[leaf_index].to_play;
+                    self.revert_virtual_loss(&path);
+                    self.backpropagate(&path, to_play, leaf.terminal_value(to_play));
+                    simulations += 1;
+                    if simulations >= self.config.simulations {
+                        break;
+                    }
+                    continue;
+                }
+                if !pending.insert(leaf_index) {
+                    // the leaf is already waiting for an evaluation
+                    self.revert_virtual_loss(&path);
+                    break;
+                }
+                paths.push(path);
+                leaves.push(leaf);
+            }
+
+            if leaves.is_empty() {
+                continue;
+            }
+
+            let evaluations = evaluator.evaluate(&leaves)?;
+            for ((path, leaf), evaluation) in paths.iter().zip(leaves.iter()).zip(evaluations) {
+                let leaf_index = *path.last().unwrap();
+                self.expand(leaf_index, leaf, &evaluation);
+                self.revert_virtual_loss(path);
+                self.backpropagate(path, leaf.to_play(), evaluation.value);
+            }
+            simulations += paths.len();
+        }
+        Ok(())
+    }
+
+    // walks down the tree applying virtual loss until an unexpanded or
+    // terminal node is reached
+    fn select_leaf<G: MctsGame>(&mut self, game: &G) -> (Vec<usize>, G) {
+        let mut game = game.clone();
+        let mut path = vec![ROOT];
+        let mut index = ROOT;
+        while self.nodes[index].expanded && !self.nodes[index].terminal {
+            index = self.select_child(index);
+            self.nodes[index].virtual_visits += 1;
+            game.apply_action(self.nodes[index].action);
+            path.push(index);
+        }
+        if !self.nodes[index].expanded && game.is_terminal() {
+            let node = &mut self.nodes[index];
+            node.to_play = game.to_play();
+            node.expanded = true;
+            node.terminal = true;
+        }
+        (path, game)
+    }
+
+    fn select_child(&self, parent: usize) -> usize {
+        let parent_node = &self.nodes[parent];
+        let parent_visits = (parent_node.visits + parent_node.virtual_visits).max(1) as f32;
+        let mut best = parent_node.children[0];
+        let mut best_score = f32::NEG_INFINITY;
+        for &child in parent_node.children.iter() {
+            let node = &self.nodes[child];
+            let visits = (node.visits + node.virtual_visits) as f32;
+            let score = match self.config.selection {
+                Selection::Uct => {
+                    if visits == 0.0 {
+                        f32::INFINITY
+                    } else {
+                        self.q_value(parent, child)
+                            + self.config.exploration * (parent_visits.ln() / visits).sqrt()
+                    }
+                }
+                Selection::Puct => {
+                    self.q_value(parent, child)
+                        + self.config.exploration * node.prior * parent_visits.sqrt()
+                            / (1.0 + visits)
+                }
+            };
+            if score > best_score {
+                best_score = score;
+                best = child;
+            }
+        }
+        best
+    }
+
+    // mean value of `child` from the point of view of the player choosing
+    // at `parent`, counting pending visits as losses
+    fn q_value(&self, parent: usize, child: usize) -> f32 {
+        let node = &self.nodes[child];
+        let visits = node.visits + node.virtual_visits;
+        if visits == 0 {
+            return 0.0;
+        }
+        let value_sum = if node.to_play == self.nodes[parent].to_play {
+            node.value_sum
+        } else {
+            -node.value_sum
+        };
+        (value_sum - self.config.virtual_loss * node.virtual_visits as f32) / visits as f32
+    }
+
+    fn expand<G: MctsGame>(&mut self, index: usize, game: &G, evaluation: &Evaluation) {
+        let actions = game.legal_actions();
+        let total: f32 = evaluation.priors.iter().take(actions.len()).sum();
+        let mut children = Vec::with_capacity(actions.len());
+        for (i, action) in actions.iter().enumerate() {
+            let prior = match evaluation.priors.get(i) {
+                Some(prior) if total > 0.0 => prior / total,
+                _ => 1.0 / actions.len() as f32,
+            };
+            children.push(self.nodes.len());
+            self.nodes.push(Node::new(*action, prior));
+        }
+        let to_play = game.to_play();
+        // children start on the side of their parent until they are expanded
+        for &child in children.iter() {
+            self.nodes[child].to_play = to_play;
+        }
+        let node = &mut self.nodes[index];
+        node.to_play = to_play;
+        node.expanded = true;
+        node.terminal = game.is_terminal();
+        node.children = children;
+    }
+
+    fn revert_virtual_loss(&mut self, path: &[usize]) {
+        for &index in path[1..].iter() {
+            self.nodes[index].virtual_visits -= 1;
+        }
+    }
+
+    fn backpropagate(&mut self, path: &[usize], player: u8, value: f32) {
+        for &index in path.iter() {
+            let node = &mut self.nodes[index];
+            node.visits += 1;
+            node.value_sum += if node.to_play == player {
+                value
+            } else {
+                -value
+            };
+        }
+    }
+
+    fn add_dirichlet_noise(&mut self) {
+        let children = self.nodes[ROOT].children.clone();
+        if children.is_empty() {
+            return;
+        }
+        let mut rng = rand::thread_rng();
+        let noise: Vec<f32> = children
+            .iter()
+            .map(|_| sample_gamma(&mut rng, self.config.dirichlet_alpha))
+            .collect();
+        let total: f32 = noise.iter().sum();
+        if total <= 0.0 {
+            return;
+        }
+        let fraction = self.config.dirichlet_fraction;
+        for (child, eta) in children.iter().zip(noise) {
+            let node = &mut self.nodes[*child];
+            node.prior = (1.0 - fraction) * node.prior + fraction * eta / total;
+        }
+    }
+
+    /// Visit counts of the root children as `(action, visits)` pairs
+    pub fn visit_counts(&self) -> Vec<(usize, u32)> {
+        self.nodes[ROOT]
+            .children
+            .iter()
+            .map(|&child| (self.nodes[child].action, self.nodes[child].visits))
+            .collect()
+    }
+
+    /// Mean value of the root from the point of view of the player to move
+    pub fn root_value(&self) -> f32 {
+        let root = &self.nodes[ROOT];
+        if root.visits == 0 {
+            0.0
+        } else {
+            root.value_sum / root.visits as f32
+        }
+    }
+
+    /// Most visited root action, `None` before the first search
+    pub fn best_action(&self) -> Option<usize> {
+        self.nodes[ROOT]
+            .children
+            .iter()
+            .max_by_key(|&&child| self.nodes[child].visits)
+            .map(|&child| self.nodes[child].action)
+    }
+}
+
+// Marsaglia and Tsang's method, boosted for shapes below one
+fn sample_gamma<R: Rng>(rng: &mut R, alpha: f32) -> f32 {
+    if alpha <= 0.0 {
+        return 0.0;
+    }
+    if alpha < 1.0 {
+        let u: f32 = rng.gen_range(f32::EPSILON..1.0);
+        return sample_gamma(rng, alpha + 1.0) * u.powf(1.0 / alpha);
+    }
+    let d = alpha - 1.0 / 3.0;
+    let c = 1.0 / (9.0 * d).sqrt();
+    loop {
+        let x = sample_standard_normal(rng);
+        let v = (1.0 + c * x).powi(3);
+        if v <= 0.0 {
+            continue;
+        }
+        let u: f32 = rng.gen_range(f32::EPSILON..1.0);
+        if u.ln() < 0.5 * x * x + d - d * v + d * v.ln() {
+            return d * v;
+        }
+    }
+}
+
+// Box-Muller transform
+fn sample_standard_normal<R: Rng>(rng: &mut R) -> f32 {
+    let u1: f32 = rng.gen_range(f32::EPSILON..1.0);
+    let u2: f32 = rng.gen_range(0.0..1.0);
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f32::consts::PI * u2).cos()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::gato::Tictactoe;
+
+    fn tictactoe(moves: &[usize]) -> Tictactoe {
+        let mut game = Tictactoe::new(3, 3, 3);
+        game.reset();
+        for action in moves {
+            game.step(*action).unwrap();
+        }
+        game
+    }
+
+    fn config(selection: Selection) -> MctsConfig {
+        MctsConfig {
+            simulations: 2000,
+            selection,
+            exploration: 1.4,
+            ..MctsConfig::default()
+        }
+    }
+
+    #[test]
+    fn uct_finds_winning_move() {
+        // X X .
+        // O O .
+        // . . .
+        let game = tictactoe(&[0, 3, 1, 4]);
+        let mut mcts = Mcts::new(config(Selection::Uct));
+        mcts.search(&game, &mut RolloutEvaluator::new(1)).unwrap();
+        assert_eq!(Some(2), mcts.best_action());
+    }
+
+    #[test]
+    fn puct_blocks_losing_move() {
+        // X X .
+        // O . .
+        // . . .
+        let game = tictactoe(&[0, 3, 1]);
+        let mut mcts = Mcts::new(config(Selection::Puct));
+        mcts.search(&game, &mut RolloutEvaluator::new(1)).unwrap();
+        assert_eq!(Some(2), mcts.best_action());
+    }
+
+    #[test]
+    fn batched_search_with_noise_visits_every_simulation() {
+        let game = tictactoe(&[]);
+        let mut mcts = Mcts::new(MctsConfig {
+            simulations: 200,
+            batch_size: 8,
+            dirichlet_fraction: 0.25,
+            ..MctsConfig::default()
+        });
+        mcts.search(&game, &mut RolloutEvaluator::new(1)).unwrap();
+        let visits: u32 = mcts.visit_counts().iter().map(|(_, n)| n).sum();
+        assert!(visits > 0 && visits <= 200);
+        assert_eq!(9, mcts.visit_counts().len());
+    }
+
+    #[test]
+    fn virtual_loss_is_reverted_on_terminal_leaves() {
+        // X X .
+        // O O .
+        // . . .
+        let game = tictactoe(&[0, 3, 1, 4]);
+        let mut mcts = Mcts::new(MctsConfig {
+            simulations: 500,
+            batch_size: 4,
+            ..config(Selection::Uct)
+        });
+        mcts.search(&game, &mut RolloutEvaluator::new(1)).unwrap();
+        assert!(mcts.nodes.iter().any(|node| node.terminal));
+        assert!(mcts.nodes.iter().all(|node| node.virtual_visits == 0));
+    }
+
+    #[test]
+    fn advance_keeps_subtree() {
+        let game = tictactoe(&[]);
+        let mut mcts = Mcts::new(config(Selection::Uct));
+        mcts.search(&game, &mut RolloutEvaluator::new(1)).unwrap();
+        let action = mcts.best_action().unwrap();
+        let (_, visits) = mcts
+            .visit_counts()
+            .into_iter()
+            .find(|(a, _)| *a == action)
+            .unwrap();
+
+        mcts.advance(action);
+        assert_eq!(visits, mcts.nodes[ROOT].visits);
+        assert_eq!(8, mcts.visit_counts().len());
+    }
+}
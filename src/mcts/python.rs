@@ -0,0 +1,114 @@
+use super::{Evaluation, Evaluator, Mcts, MctsConfig, MctsGame, RolloutEvaluator, Selection};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+/// `(best_action, [(action, visits), ...], root_value)`
+pub type SearchResult = (Option<usize>, Vec<(usize, u32)>, f32);
+
+/// Evaluates leaves with a Python callable. The callable receives a list of
+/// `(observation, legal_actions, to_play)` tuples and must return a list of
+/// `(priors, value)` tuples, with `priors` aligned to `legal_actions`.
+pub struct PyEvaluator<'p> {
+    py: Python<'p>,
+    callback: &'p PyObject,
+}
+
+impl<'p, G> Evaluator<G> for PyEvaluator<'p>
+where
+    G: MctsGame,
+    G::Observation: IntoPy<PyObject>,
+{
+    type Error = PyErr;
+
+    fn evaluate(&mut self, games: &[G]) -> PyResult<Vec<Evaluation>> {
+        let batch: Vec<(G::Observation, Vec<usize>, u8)> = games
+            .iter()
+            .map(|game| (game.observation(), game.legal_actions(), game.to_play()))
+            .collect();
+        let result: Vec<(Vec<f32>, f32)> =
+            self.callback.call1(self.py, (batch,))?.extract(self.py)?;
+        if result.len() != games.len() {
+            return Err(PyValueError::new_err(format!(
+                "evaluator returned {} evaluations for {} positions",
+                result.len(),
+                games.len()
+            )));
+        }
+        Ok(result
+            .into_iter()
+            .map(|(priors, value)| Evaluation { priors, value })
+            .collect())
+    }
+}
+
+/// Builds a configuration from a dict such as
+/// `{"simulations": 400, "selection": "uct", "dirichlet_fraction": 0.25}`.
+/// Missing keys keep their default value.
+pub fn config_from_dict(dict: &PyDict) -> PyResult<MctsConfig> {
+    let mut config = MctsConfig::default();
+    if let Some(value) = dict.get_item("simulations") {
+        config.simulations = value.extract()?;
+    }
+    if let Some(value) = dict.get_item("selection") {
+        config.selection = match value.extract::<&str>()? {
+            "uct" => Selection::Uct,
+            "puct" => Selection::Puct,
+            other => {
+                return Err(PyValueError::new_err(format!(
+                    "unknown selection '{}', expected 'uct' or 'puct'",
+                    other
+                )))
+            }
+        };
+    }
+    if let Some(value) = dict.get_item("exploration") {
+        config.exploration = value.extract()?;
+    }
+    if let Some(value) = dict.get_item("dirichlet_alpha") {
+        config.dirichlet_alpha = value.extract()?;
+    }
+    if let Some(value) = dict.get_item("dirichlet_fraction") {
+        config.dirichlet_fraction = value.extract()?;
+    }
+    if let Some(value) = dict.get_item("virtual_loss") {
+        config.virtual_loss = value.extract()?;
+    }
+    if let Some(value) = dict.get_item("batch_size") {
+        config.batch_size = value.extract()?;
+    }
+    if let Some(value) = dict.get_item("reuse_tree") {
+        config.reuse_tree = value.extract()?;
+    }
+    Ok(config)
+}
+
+/// Runs a search from `game` and returns the most visited action, the
+/// visit counts of the root children and the root value. Without an
+/// `evaluator` the leaves are scored with `rollouts` random playouts.
+pub fn search<G>(
+    py: Python,
+    mcts: &mut Mcts,
+    game: &G,
+    evaluator: Option<PyObject>,
+    rollouts: usize,
+) -> PyResult<SearchResult>
+where
+    G: MctsGame,
+    G::Observation: IntoPy<PyObject>,
+{
+    match evaluator {
+        Some(callback) => {
+            let mut evaluator = PyEvaluator {
+                py,
+                callback: &callback,
+            };
+            mcts.search(game, &mut evaluator)?;
+        }
+        None => match mcts.search(game, &mut RolloutEvaluator::new(rollouts)) {
+            Ok(()) => {}
+            Err(e) => match e {},
+        },
+    }
+    Ok((mcts.best_action(), mcts.visit_counts(), mcts.root_value()))
+}
@@ -0,0 +1,192 @@
+// Board symmetries
+// ---------------------------------------------------------
+// The eight symmetries of a square (the dihedral group), as maps of the
+// cells of a grid. Engines use them to augment training data, an
+// observation and a policy over the cells being mapped together, and to
+// pick one representative among equivalent positions.
+
+/// A symmetry of a board with `rows` rows and `cols` columns, rotations
+/// being clockwise
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Symmetry {
+    Identity,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    /// Upside down, the first row becoming the last
+    FlipRows,
+    /// Left to right, the first column becoming the last
+    FlipColumns,
+    /// Along the main diagonal
+    Transpose,
+    /// Along the anti-diagonal
+    AntiTranspose,
+}
+
+impl Symmetry {
+    pub const ALL: [Symmetry; 8] = [
+        Symmetry::Identity,
+        Symmetry::Rotate90,
+        Symmetry::Rotate180,
+        Symmetry::Rotate270,
+        Symmetry::FlipRows,
+        Symmetry::FlipColumns,
+        Symmetry::Transpose,
+        Symmetry::AntiTranspose,
+    ];
+
+    /// The symmetries mapping a `rows` by `cols` board onto itself: all
+    /// eight for a square board, the four keeping the shape otherwise.
+    /// `Identity` comes first.
+    pub fn of_board(rows: usize, cols: usize) -> Vec<Symmetry> {
+        if rows == cols {
+            Symmetry::ALL.to_vec()
+        } else {
+            vec![
+                Symmetry::Identity,
+                Symmetry::Rotate180,
+                Symmetry::FlipRows,
+                Symmetry::FlipColumns,
+            ]
+        }
+    }
+
+    /// Position in `ALL`, the index the Python engines use
+    pub fn index(&self) -> usize {
+        Symmetry::ALL.iter().position(|s| s == self).unwrap()
+    }
+
+    pub fn from_index(index: usize) -> Option<Symmetry> {
+        Symmetry::ALL.get(index).copied()
+    }
+
+    pub fn inverse(&self) -> Symmetry {
+        match self {
+            Symmetry::Rotate90 => Symmetry::Rotate270,
+            Symmetry::Rotate270 => Symmetry::Rotate90,
+            other => *other,
+        }
+    }
+
+    /// Whether rows and columns swap, so that the board changes shape
+    /// unless it is square
+    pub fn swaps_axes(&self) -> bool {
+        matches!(
+            self,
+            Symmetry::Rotate90
+                | Symmetry::Rotate270
+                | Symmetry::Transpose
+                | Symmetry::AntiTranspose
+        )
+    }
+
+    /// Where the cell (row, col) of a `rows` by `cols` board goes
+    pub fn apply(&self, row: usize, col: usize, rows: usize, cols: usize) -> (usize, usize) {
+        match self {
+            Symmetry::Identity => (row, col),
+            Symmetry::Rotate90 => (col, rows - 1 - row),
+            Symmetry::Rotate180 => (rows - 1 - row, cols - 1 - col),
+            Symmetry::Rotate270 => (cols - 1 - col, row),
+            Symmetry::FlipRows => (rows - 1 - row, col),
+            Symmetry::FlipColumns => (row, cols - 1 - col),
+            Symmetry::Transpose => (col, row),
+            Symmetry::AntiTranspose => (cols - 1 - col, rows - 1 - row),
+        }
+    }
+
+    /// `grid` with every cell moved to where the symmetry takes it
+    pub fn transform_grid<T: Copy + Default>(&self, grid: &[Vec<T>]) -> Vec<Vec<T>> {
+        let rows = grid.len();
+        let cols = grid.first().map_or(0, |row| row.len());
+        let (new_rows, new_cols) = if self.swaps_axes() {
+            (cols, rows)
+        } else {
+            (rows, cols)
+        };
+        let mut transformed = vec![vec![T::default(); new_cols]; new_rows];
+        for (row, cells) in grid.iter().enumerate() {
+            for (col, item) in cells.iter().enumerate() {
+                let (r, c) = self.apply(row, col, rows, cols);
+                transformed[r][c] = *item;
+            }
+        }
+        transformed
+    }
+
+    /// The same as `transform_grid` for cells laid out row after row, such
+    /// as a policy over the actions of a board game
+    pub fn transform_cells<T: Copy + Default>(
+        &self,
+        cells: &[T],
+        rows: usize,
+        cols: usize,
+    ) -> Vec<T> {
+        let new_cols = if self.swaps_axes() { rows } else { cols };
+        let mut transformed = vec![T::default(); cells.len()];
+        for (i, item) in cells.iter().enumerate() {
+            let (r, c) = self.apply(i / cols, i % cols, rows, cols);
+            transformed[r * new_cols + c] = *item;
+        }
+        transformed
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn numbered(rows: usize, cols: usize) -> Vec<Vec<usize>> {
+        (0..rows)
+            .map(|row| (0..cols).map(|col| row * cols + col).collect())
+            .collect()
+    }
+
+    #[test]
+    fn rotations_of_a_square() {
+        // 0 1 2      6 3 0
+        // 3 4 5  ->  7 4 1
+        // 6 7 8      8 5 2
+        let grid = numbered(3, 3);
+        assert_eq!(
+            vec![vec![6, 3, 0], vec![7, 4, 1], vec![8, 5, 2]],
+            Symmetry::Rotate90.transform_grid(&grid)
+        );
+        let twice = Symmetry::Rotate90.transform_grid(&Symmetry::Rotate90.transform_grid(&grid));
+        assert_eq!(Symmetry::Rotate180.transform_grid(&grid), twice);
+    }
+
+    #[test]
+    fn every_symmetry_is_distinct_and_undone_by_its_inverse() {
+        let grid = numbered(4, 4);
+        let mut images = Vec::new();
+        for symmetry in Symmetry::ALL.iter() {
+            let image = symmetry.transform_grid(&grid);
+            assert_eq!(grid, symmetry.inverse().transform_grid(&image));
+            assert!(!images.contains(&image), "{:?}", symmetry);
+            images.push(image);
+            assert_eq!(Some(*symmetry), Symmetry::from_index(symmetry.index()));
+        }
+    }
+
+    #[test]
+    fn cells_follow_the_grid() {
+        let grid = numbered(2, 3);
+        let cells: Vec<usize> = grid.iter().flatten().copied().collect();
+        for symmetry in Symmetry::ALL.iter() {
+            let flat: Vec<usize> = symmetry
+                .transform_grid(&grid)
+                .into_iter()
+                .flatten()
+                .collect();
+            assert_eq!(flat, symmetry.transform_cells(&cells, 2, 3));
+        }
+    }
+
+    #[test]
+    fn rectangles_keep_their_shape() {
+        let symmetries = Symmetry::of_board(2, 5);
+        assert_eq!(4, symmetries.len());
+        assert!(symmetries.iter().all(|symmetry| !symmetry.swaps_axes()));
+        assert_eq!(8, Symmetry::of_board(3, 3).len());
+    }
+}
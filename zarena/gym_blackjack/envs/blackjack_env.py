@@ -8,9 +8,29 @@ class BlackjackEnv(gym.Env):
     Game wrapper.
     """
 
-    def __init__(self, n_players=1):
+    def __init__(
+        self,
+        n_players=1,
+        decks=1,
+        penetration=1.0,
+        persistent=False,
+        rules=None,
+        observation="legacy",
+        counts=False,
+    ):
         # engine
-        self.engine = BlackjackEngine(n_players)
+        # decks: decks in the shoe, from 1 to 8
+        # penetration: fraction of the shoe dealt before the cut card comes out
+        # persistent: keep the shoe across rounds until the cut card comes out, instead of
+        #   shuffling it before every round
+        # rules: dict of table rules, the keys left out keeping their default value, unknown keys
+        #   raising ValueError
+        # observation: the "legacy" 3x3x3 array, or the "features" or "one_hot" vectors
+        # counts: end the observation vectors with the running and true counts and the cards
+        #   of each rank left in the shoe
+        self.engine = BlackjackEngine(
+            n_players, decks, penetration, persistent, rules, observation, counts
+        )
 
     def step(self, action):
         """
@@ -20,7 +40,11 @@ class BlackjackEnv(gym.Env):
             action : action of the action_space to take.
 
         Returns:
-            The new observation, the reward and a boolean if the game has ended.
+            The new observation, the reward and a boolean if the game has ended. Rewards are
+            floats, surrender and insurance being worth half a bet.
+
+        Raises:
+            ValueError: for an action that is not one of legal_actions.
         """
         observation, reward, done = self.engine.step(action)
         return observation, reward, done, None
@@ -62,6 +86,21 @@ class BlackjackEnv(gym.Env):
         """
         return self.engine.get_state()
 
+    def observation_size(self):
+        """
+        Returns:
+            The length of the observation vectors, None for the legacy observation.
+        """
+        return self.engine.observation_size()
+
+    def get_shoe(self):
+        """
+        Returns:
+            The cards in the full shoe, the cards left to deal and a boolean if the cut card
+            came out, the shoe being shuffled at the next reset.
+        """
+        return self.engine.get_shoe()
+
     def get_total_players(self):
         """
         Returns:
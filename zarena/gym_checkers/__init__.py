@@ -1,4 +1,5 @@
 from zarena.zarena import CheckersEngine  # rust module
+from zarena.zarena import IllegalMoveError, MustJumpError, InvalidStateError  # engine errors
 from zarena.gym_checkers.envs.checkers import CheckersEnv  # envs
 from gym.envs.registration import register  # to register envs
 
@@ -8,9 +8,13 @@ class CheckersEnv(gym.Env):
     Game wrapper.
     """
 
-    def __init__(self):
+    def __init__(self, perspective=False, draw_move_limit=None, variant="english", material_reward=0.0):
         # engine
-        self.engine = CheckersEngine()
+        # perspective: observe the board from the side of the player to move
+        # draw_move_limit: moves per player without captures or man moves before a draw, e.g. 40, None to disable
+        # variant: "english", "international", "brazilian", "russian" or "italian"
+        # material_reward: reward for every man won (1.5 for a king) on each move, 0 for terminal rewards only
+        self.engine = CheckersEngine(perspective, draw_move_limit, variant, material_reward)
 
     def step(self, action):
         """
@@ -20,10 +24,19 @@ class CheckersEnv(gym.Env):
             action : action of the action_space to take.
 
         Returns:
-            The new observation, the reward and a boolean if the game has ended.
+            The new observation, the reward of the player who moved, a boolean if the game has ended
+            and an info dict with the rewards of both players indexed by to_play ("rewards"), the
+            winner ("winner", None for a draw or a game in progress) and the reason the game ended
+            ("reason": "no_pieces", "blocked", "draw", "endgame" or None).
+
+        Raises:
+            MustJumpError: a capture was available and has to be played.
+            IllegalMoveError: the action is not legal.
         """
-        observation, reward, done = self.engine.step(action)
-        return observation, reward, done, None
+        player = self.engine.to_play()
+        observation, rewards, done, winner, reason = self.engine.step(action)
+        info = {"rewards": rewards, "winner": winner, "reason": reason}
+        return observation, rewards[player], done, info
 
     def to_play(self):
         """
@@ -47,6 +60,39 @@ class CheckersEnv(gym.Env):
         """
         return self.engine.legal_actions()
 
+    def legal_action_mask(self):
+        """
+        Returns:
+            A list of 1024 booleans indexed by from * 32 + to, in the same frame as the observation.
+        """
+        return self.engine.legal_action_mask()
+
+    def flip_action(self, action):
+        """
+        Map an action to the same move seen from the opposite side of the board.
+        """
+        return self.engine.flip_action(action)
+
+    def symmetries(self, observation, policy):
+        """
+        Args:
+            observation: planes laid out as the observations of step and reset.
+            policy: one entry per index of legal_action_mask.
+
+        Returns:
+            (observation, policy) for the given pair and for the same position with the colours
+            swapped, the board rotated by 180 degrees and the other player to move.
+        """
+        return self.engine.symmetries(observation, policy)
+
+    def canonical_state(self):
+        """
+        Returns:
+            ((to_play, board_int), flipped): the state with player 1 to move, the colours being
+            swapped when player 2 is to move. Actions of a flipped state map back with flip_action.
+        """
+        return self.engine.canonical_state()
+
     def reset(self):
         """
         Reset the game for a new game.
@@ -66,12 +112,162 @@ class CheckersEnv(gym.Env):
     def set_state(self, game_state):
         """
         Args:
-            game_state: the state to be established in the game
+            game_state: the state to be established in the game, the rows of the board starting from the
+                first player's back row, which is the reverse of the rows of get_state
         Returns:
             observation of the game.
+        Raises:
+            InvalidStateError: the board has the wrong size, unknown piece codes, pieces on light
+                squares, too many pieces for a player or men on their crowning row.
         """
         state = (game_state["to_play"], game_state["board_int"])
         return self.engine.set_state(state)
 
+    def set_mcts_config(self, config):
+        """
+        Args:
+            config: dict with the search parameters (simulations, selection, exploration,
+                dirichlet_alpha, dirichlet_fraction, virtual_loss, batch_size, reuse_tree)
+        """
+        self.engine.set_mcts_config(config)
+
+    def mcts_search(self, evaluator=None, rollouts=1):
+        """
+        Run Monte Carlo Tree Search from the current position.
+
+        Args:
+            evaluator: optional callable receiving a list of (observation, legal_actions, to_play)
+                and returning a list of (priors, value). Random rollouts are used when it is None.
+            rollouts: number of random playouts per leaf when no evaluator is given.
+
+        Returns:
+            The most visited action, the visit counts as (action, visits) pairs and the root value.
+        """
+        return self.engine.mcts_search(evaluator, rollouts)
+
+    def best_action(self, depth=6, time_limit=None, weights=None):
+        """
+        Search the current position with alpha-beta (english variant only).
+
+        Args:
+            depth: number of plies to search.
+            time_limit: optional number of seconds after which the search stops.
+            weights: optional dict overriding the evaluation weights (man, king, back_rank, mobility).
+
+        Returns:
+            The chosen action, or None when there is no legal action.
+
+        Raises:
+            ValueError: time_limit is negative, infinite or NaN.
+        """
+        return self.engine.best_action(depth, time_limit, weights)
+
+    def perft(self, depth):
+        """
+        Count the move sequences of depth plies from the current position, to check move generation.
+        """
+        return self.engine.perft(depth)
+
+    @staticmethod
+    def build_endgame(max_pieces, path):
+        """
+        Solve every english position with up to max_pieces pieces (at most 4) and save the database.
+
+        Returns:
+            The number of won or lost positions.
+        """
+        return CheckersEngine.build_endgame(max_pieces, path)
+
+    def load_endgame(self, path, adjudicate=True):
+        """
+        Load an endgame database written by build_endgame (english variant only).
+
+        Args:
+            path: file of the database.
+            adjudicate: end the game as soon as a position in the database is reached, the
+                step info reason being "endgame".
+        """
+        self.engine.load_endgame(path, adjudicate)
+
+    def probe_endgame(self):
+        """
+        Returns:
+            ("win", "loss" or "draw", plies to the end) for the player to move with best play, or
+            None when no database is loaded or there are too many pieces left.
+        """
+        return self.engine.probe_endgame()
+
+    def history(self):
+        """
+        Returns:
+            The actions played since the start, or since the last set_state/set_fen.
+        """
+        return self.engine.history()
+
+    def undo(self):
+        """
+        Take back the last action.
+
+        Returns:
+            The new observation, or None when there is no action to take back.
+        """
+        return self.engine.undo()
+
+    def redo(self):
+        """
+        Play again the last action taken back by undo.
+
+        Returns:
+            The new observation, or None when there is no action to play again.
+        """
+        return self.engine.redo()
+
+    def get_fen(self):
+        """
+        Returns:
+            The PDN FEN of the current position, e.g. "B:W21,22:B1,2" (english variant only).
+        """
+        return self.engine.get_fen()
+
+    def set_fen(self, fen):
+        """
+        Set the position from a PDN FEN, "B" (Black) being the first player.
+
+        Returns:
+            The observation of the new position.
+        """
+        return self.engine.set_fen(fen)
+
+    def to_pdn(self, tags=None):
+        """
+        Args:
+            tags: optional list of (name, value) pairs, e.g. [("Event", "Training")]. A Result tag, or
+                the result read by load_pdn, is kept while the board does not show the game as over.
+                FEN and SetUp tags are replaced by the starting position of the game.
+
+        Returns:
+            The moves played since the start, or since the last set_state/set_fen, as a PDN game.
+        """
+        return self.engine.to_pdn(tags or [])
+
+    def load_pdn(self, text):
+        """
+        Replay the first game of a PDN text.
+
+        Returns:
+            The actions of the game.
+        """
+        return self.engine.load_pdn(text)
+
+    @staticmethod
+    def read_pdn(text):
+        """
+        Read every game of a PDN text.
+
+        Returns:
+            A list of (tags, actions, result), the actions starting from the FEN tag position if any.
+        """
+        return CheckersEngine.read_pdn(text)
+
     def print(self):
         self.engine.print()
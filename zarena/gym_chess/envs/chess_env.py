@@ -610,6 +610,44 @@ class ChessEnv(gym.Env):
             self.board,
         ]
 
+    def mirror_state(self, state=None):
+        """
+        Returns:
+            The state with the colours swapped: ranks mirrored, pieces and castling rights given
+            to the other player and the other player to move.
+        """
+        if state is None:
+            state = self.state
+        return self.engine.mirror_state(state)
+
+    def canonical_state(self, state=None):
+        """
+        Returns:
+            (state, flipped): the state with white to move, mirrored when black is to move.
+            Actions of a flipped state map back with mirror_action.
+        """
+        if state is None:
+            state = self.state
+        return self.engine.canonical_state(state)
+
+    def mirror_action(self, action):
+        """
+        Map an action to the same move on the board of mirror_state.
+        """
+        return self.engine.mirror_action(action)
+
+    def symmetries(self, observation, policy):
+        """
+        Args:
+            observation: list of boards, as returned by get_observation.
+            policy: one entry per action, 64 * 64 + 5 of them.
+
+        Returns:
+            (observation, policy) for the given pair and its colour flip.
+        """
+        observation = [[list(row) for row in board] for board in observation]
+        return self.engine.symmetries(observation, list(policy))
+
     def legal_actions(self, player=None):
         if player == None:
             player = self.current_player
@@ -0,0 +1,57 @@
+from zarena.gym_chess import ChessEnv
+from zarena.gym_chess.envs.chess_env import BLACK, DEFAULT_BOARD, WHITE
+from zarena.gym_chess.test.utils import run_test_funcs
+
+
+NUMBER_ACTIONS = 64 * 64 + 5
+
+
+# The initial position is its own colour flip
+def test_mirror_initial_state():
+    env = ChessEnv()
+    env.reset()
+    mirrored = env.mirror_state()
+    assert mirrored["board"] == DEFAULT_BOARD
+    assert mirrored["current_player"] == BLACK
+    state, flipped = env.canonical_state()
+    assert not flipped
+    assert state["current_player"] == WHITE
+
+
+# Black's moves are white's moves on the mirrored board
+def test_mirrored_moves():
+    env = ChessEnv()
+    env.reset()
+    env.step(env.move_to_action(((6, 4), (4, 4))))
+    state, flipped = env.canonical_state()
+    assert flipped
+    black_actions = sorted(
+        env.mirror_action(env.move_to_action(move))
+        for move in env.get_possible_moves(player=BLACK)
+    )
+    white_actions = sorted(
+        env.move_to_action(move) for move in env.get_possible_moves(state, WHITE)
+    )
+    assert black_actions == white_actions
+
+
+# Castles swap colour and the policy follows its moves
+def test_symmetries_of_a_policy():
+    env = ChessEnv()
+    observation = env.reset()
+    policy = [0.0] * NUMBER_ACTIONS
+    e2e4 = env.move_to_action(((6, 4), (4, 4)))
+    policy[e2e4] = 0.5
+    policy[64 * 64] = 0.5
+    samples = env.symmetries(observation, policy)
+    assert len(samples) == 2
+    mirrored_observation, mirrored_policy = samples[1]
+    # white's back rank comes up as black's
+    assert mirrored_observation[0][0] == [-3, -5, -4, -2, -1, -4, -5, -3]
+    assert mirrored_policy[env.move_to_action(((1, 4), (3, 4)))] == 0.5
+    assert mirrored_policy[64 * 64 + 2] == 0.5
+    assert env.mirror_action(env.mirror_action(e2e4)) == e2e4
+
+
+if __name__ == "__main__":
+    run_test_funcs(__name__)
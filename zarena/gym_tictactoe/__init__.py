@@ -1,8 +1,20 @@
-from zarena.zarena import TictactoeEngine  # rust module
+from zarena.zarena import TictactoeEngine, UltimateTictactoeEngine  # rust module
 from zarena.gym_tictactoe.envs.tictactoe import TictactoeEnv  # envs
+from zarena.gym_tictactoe.envs.ultimate import UltimateTictactoeEnv
 from gym.envs.registration import register  # to register envs
 
 register(
     id="GatoEnv-v2",
     entry_point="zarena.gym_tictactoe:TictactoeEnv",
 )
+
+register(
+    id="GomokuEnv-v1",
+    entry_point="zarena.gym_tictactoe:TictactoeEnv",
+    kwargs={"rows": 15, "cols": 15, "k": 5},
+)
+
+register(
+    id="UltimateGatoEnv-v1",
+    entry_point="zarena.gym_tictactoe:UltimateTictactoeEnv",
+)
@@ -1 +1,2 @@
 from zarena.gym_tictactoe.envs.tictactoe import TictactoeEnv
+from zarena.gym_tictactoe.envs.ultimate import UltimateTictactoeEnv
@@ -8,9 +8,17 @@ class TictactoeEnv(gym.Env):
     Game wrapper.
     """
 
-    def __init__(self, n_players=1):
+    def __init__(self, rows=3, cols=3, k=3, first_player=0, illegal_action_penalty=None):
         # engine
-        self.engine = TictactoeEngine(n_players)
+        # rows, cols: size of the board
+        # k: marks in a row, column or diagonal needed to win
+        # first_player: 0 for crosses or 1 for noughts to make the first mark
+        # illegal_action_penalty: reward of a player forfeiting the game with an illegal
+        #   action, None to raise ValueError instead
+        self.rows = rows
+        self.cols = cols
+        self.k = k
+        self.engine = TictactoeEngine(rows, cols, k, first_player, illegal_action_penalty)
 
     def step(self, action):
         """
@@ -20,10 +28,20 @@ class TictactoeEnv(gym.Env):
             action : action of the action_space to take.
 
         Returns:
-            The new observation, the reward and a boolean if the game has ended.
+            The new observation, the reward of the player who moved, a boolean if the game has ended
+            and an info dict with the rewards of both players indexed by to_play ("rewards": 1 for
+            the winner, 0 for the loser and 0.5 each for a draw), the winner ("winner", None for
+            a draw or a game in progress) and whether the action was illegal ("illegal_action").
+
+        Raises:
+            ValueError: for an illegal action without an illegal_action_penalty, or once the game
+                is over.
         """
-        observation, reward, done = self.engine.step(action)
-        return observation, reward, done, None
+        player = self.engine.to_play()
+        illegal_action = action not in self.engine.legal_actions()
+        observation, rewards, done, winner = self.engine.step(action)
+        info = {"rewards": rewards, "winner": winner, "illegal_action": illegal_action}
+        return observation, rewards[player], done, info
 
     def to_play(self):
         """
@@ -66,15 +84,99 @@ class TictactoeEnv(gym.Env):
     def set_state(self, game_state):
         """
         Args:
-            game_state: the state to be established in the game
+            game_state: the state to be established in the game, the winner and the end of the
+                game being worked out from the board
         Returns:
             observation of the game.
         """
         state = (game_state["to_play"], game_state["board_int"])
         return self.engine.set_state(state)
 
+    def undo(self):
+        """
+        Take back the last action.
+
+        Returns:
+            The new observation, or None when there is no action to take back.
+        """
+        return self.engine.undo()
+
     def expert_action(self):
         return self.engine.expert_action()
 
+    def optimal_action(self):
+        """
+        Returns:
+            An action with perfect play, winning as fast or losing as slowly as possible, or None
+            once the game is over. Positions with more than 12 empty cells raise ValueError.
+        """
+        return self.engine.optimal_action()
+
+    def action_values(self):
+        """
+        Returns:
+            (action, value) pairs for every legal action, the value being 1 for a win, 0 for a
+            draw and -1 for a loss with perfect play.
+        """
+        return self.engine.action_values()
+
+    def value_table(self):
+        """
+        Returns:
+            (board_int, to_play, [(action, value), ...]) for every position reachable from the
+            start where the game is not over, boards laid out as in get_state.
+        """
+        return self.engine.value_table()
+
+    def symmetries(self, observation, policy):
+        """
+        Args:
+            observation: planes laid out as the observations of step and reset.
+            policy: one entry per action.
+
+        Returns:
+            (observation, policy) for every symmetry of the board, the given pair first: eight on a
+            square board, four on a rectangular one.
+        """
+        return self.engine.symmetries(observation, policy)
+
+    def canonical_state(self):
+        """
+        Returns:
+            (to_play, board_int, symmetry): the representative of the current position among its
+            symmetric equivalents, shared by all of them, and the index of the symmetry taking the
+            board there.
+        """
+        return self.engine.canonical_state()
+
+    def transform_action(self, action, symmetry, inverse=False):
+        """
+        Map an action through the symmetry of the given index, or its inverse to bring an action
+        of the canonical state back to the current board.
+        """
+        return self.engine.transform_action(action, symmetry, inverse)
+
+    def set_mcts_config(self, config):
+        """
+        Args:
+            config: dict with the search parameters (simulations, selection, exploration,
+                dirichlet_alpha, dirichlet_fraction, virtual_loss, batch_size, reuse_tree)
+        """
+        self.engine.set_mcts_config(config)
+
+    def mcts_search(self, evaluator=None, rollouts=1):
+        """
+        Run Monte Carlo Tree Search from the current position.
+
+        Args:
+            evaluator: optional callable receiving a list of (observation, legal_actions, to_play)
+                and returning a list of (priors, value). Random rollouts are used when it is None.
+            rollouts: number of random playouts per leaf when no evaluator is given.
+
+        Returns:
+            The most visited action, the visit counts as (action, visits) pairs and the root value.
+        """
+        return self.engine.mcts_search(evaluator, rollouts)
+
     def print(self):
         self.engine.print()
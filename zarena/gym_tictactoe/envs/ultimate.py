@@ -0,0 +1,115 @@
+import gym
+from zarena.gym_tictactoe import UltimateTictactoeEngine
+
+# ULTIMATE TICTACTOE GYM ENVIRONMENT CLASS
+# ---------------------------
+class UltimateTictactoeEnv(gym.Env):
+    """
+    Game wrapper.
+    """
+
+    def __init__(self):
+        # engine
+        # nine 3x3 boards, actions are the cells of the whole 9x9 board: row * 9 + col
+        self.engine = UltimateTictactoeEngine()
+
+    def step(self, action):
+        """
+        Apply action to the game.
+
+        Args:
+            action : action of the action_space to take.
+
+        Returns:
+            The new observation, the reward of the player who moved, a boolean if the game has ended
+            and an info dict with the rewards of both players indexed by to_play ("rewards": 1 for
+            the winner, 0 for the loser and 0.5 each for a draw) and the winner ("winner", None for
+            a draw or a game in progress).
+
+        Raises:
+            ValueError: for an action outside the board, on a taken cell, outside the board to
+                play or on a board already decided, or once the game is over.
+        """
+        player = self.engine.to_play()
+        observation, rewards, done, winner = self.engine.step(action)
+        info = {"rewards": rewards, "winner": winner}
+        return observation, rewards[player], done, info
+
+    def to_play(self):
+        """
+        Return the current player.
+
+        Returns:
+            The current player, it should be an element of the players list in the config.
+        """
+        return self.engine.to_play()
+
+    def legal_actions(self):
+        """
+        The empty cells of the board the player to move is sent to, or of every board still open
+        when that board is won or full.
+
+        Returns:
+            An array of integers, subset of the action space.
+        """
+        return self.engine.legal_actions()
+
+    def reset(self):
+        """
+        Reset the game for a new game.
+
+        Returns:
+            Initial observation of the game: crosses, noughts and the cells that may be played.
+        """
+        return self.engine.reset()
+
+    def get_state(self):
+        """
+        Returns:
+            (turn, to_play, board_int, next_board, winner, done), next_board being the board the
+            player to move is sent to or None when any open board will do.
+        """
+        return self.engine.get_state()
+
+    def set_state(self, game_state):
+        """
+        Args:
+            game_state: the state to be established in the game, with the keys to_play,
+                board_int and next_board
+        Returns:
+            observation of the game.
+        """
+        state = (
+            game_state["to_play"],
+            game_state["board_int"],
+            game_state.get("next_board"),
+        )
+        return self.engine.set_state(state)
+
+    def expert_action(self):
+        return self.engine.expert_action()
+
+    def set_mcts_config(self, config):
+        """
+        Args:
+            config: dict with the search parameters (simulations, selection, exploration,
+                dirichlet_alpha, dirichlet_fraction, virtual_loss, batch_size, reuse_tree)
+        """
+        self.engine.set_mcts_config(config)
+
+    def mcts_search(self, evaluator=None, rollouts=1):
+        """
+        Run Monte Carlo Tree Search from the current position.
+
+        Args:
+            evaluator: optional callable receiving a list of (observation, legal_actions, to_play)
+                and returning a list of (priors, value). Random rollouts are used when it is None.
+            rollouts: number of random playouts per leaf when no evaluator is given.
+
+        Returns:
+            The most visited action, the visit counts as (action, visits) pairs and the root value.
+        """
+        return self.engine.mcts_search(evaluator, rollouts)
+
+    def print(self):
+        self.engine.print()